@@ -1,12 +1,110 @@
-#[allow(non_snake_case)]
+#[allow(non_snake_case, clippy::module_inception)]
 pub mod DTOs {
+  use std::collections::{HashMap, HashSet};
   use std::path::Path;
-  use std::fs;
-  use std::time::Duration;
-  use std::thread::sleep;
-  use serde_json::Value;
+  use serde_json::{json, Value};
   use serde::{Deserialize, Serialize};
-  use spinner::SpinnerBuilder;
+  use serde::de::DeserializeOwned;
+  use crate::api::{Api, ApiResponse};
+  use crate::config::Config;
+  use crate::error::RecError;
+  use crate::storage::{Storage, read_json};
+
+  #[derive(Deserialize)]
+  #[serde(rename_all = "camelCase")]
+  struct ApiProblem {
+    contest_id: Option<i64>,
+    index: String,
+    name: String,
+    rating: Option<i64>,
+    #[serde(default)]
+    tags: Vec<String>,
+  }
+
+  impl ApiProblem {
+    //problems without a rating or a contest can't be recommended
+    fn to_dto(&self) -> Option<ProblemDTO> {
+      Some(ProblemDTO {
+        contest_id: self.contest_id?,
+        index: self.index.clone(),
+        name: self.name.clone(),
+        rating: self.rating?,
+        tags: self.tags.clone(),
+        solved_count: None,
+      })
+    }
+  }
+
+  #[derive(Deserialize)]
+  #[serde(rename_all = "camelCase")]
+  struct ApiProblemStatistics {
+    contest_id: Option<i64>,
+    index: String,
+    solved_count: i64,
+  }
+
+  #[derive(Deserialize)]
+  #[serde(rename_all = "camelCase")]
+  struct ApiProblemset {
+    problems: Vec<ApiProblem>,
+    #[serde(default)]
+    problem_statistics: Vec<ApiProblemStatistics>,
+  }
+
+  #[derive(Deserialize)]
+  struct ApiContest {
+    id: i64,
+    name: String,
+    #[serde(rename = "type")]
+    contest_type: String,
+    //1 to 5 stars, only gym contests have it
+    difficulty: Option<i64>,
+    #[serde(rename = "startTimeSeconds")]
+    start_time: Option<i64>,
+  }
+
+  #[derive(Deserialize)]
+  struct ApiProblemResult {
+    points: f64,
+  }
+
+  #[derive(Deserialize)]
+  #[serde(rename_all = "camelCase")]
+  struct ApiRanklistRow {
+    problem_results: Vec<ApiProblemResult>,
+  }
+
+  #[derive(Deserialize)]
+  struct ApiStandings {
+    //standings cached before the contest was read from them don't have it
+    contest: Option<ApiContest>,
+    problems: Vec<ApiProblem>,
+    rows: Vec<ApiRanklistRow>,
+  }
+
+  #[derive(Deserialize)]
+  #[serde(rename_all = "camelCase")]
+  struct ApiSubmission {
+    id: Option<i64>,
+    creation_time_seconds: Option<i64>,
+    problem: ApiProblem,
+    verdict: Option<String>,
+    #[serde(default)]
+    passed_test_count: i64,
+  }
+
+  #[derive(Deserialize)]
+  #[serde(rename_all = "camelCase")]
+  struct ApiUser {
+    handle: String,
+    rank: Option<String>,
+    rating: Option<i64>,
+    max_rank: Option<String>,
+    max_rating: Option<i64>,
+  }
+
+  //gym contest ids start here, regular contests count up from 1
+  pub const GYM_CONTEST_ID: i64 = 100000;
 
   #[derive(Serialize, Deserialize)]
   pub struct ProblemDTO {
@@ -14,40 +112,45 @@ pub mod DTOs {
     pub index: String,
     pub name: String,
     pub rating: i64,
+    pub tags: Vec<String>,
+    //how many users solved it, from problemStatistics or the standings of a gym contest
+    pub solved_count: Option<i64>,
+  }
+
+  fn read_cache<T: DeserializeOwned>(path: &Path) -> Result<T, RecError> {
+    let source = path.display().to_string();
+    let envelope: ApiResponse<T> = read_json(path).map_err(|error| match error {
+      RecError::Json(source, detail) => RecError::Json(source, detail + " (run \"rec update\" to refetch it)"),
+      error => error,
+    })?;
+    envelope.into_result(&source)
   }
 
   #[allow(non_snake_case)]
-  pub fn update_problemDTOs() {
-    let spin = SpinnerBuilder::new("fetching problem data...".into()).start();
-    sleep(Duration::from_secs(2));
-    let response = reqwest::blocking::get("https://codeforces.com/api/problemset.problems").unwrap();
-    spin.close();
-    print!("\r                                   \r");
-    let res: Value = response.json().unwrap();  
-    fs::write("problems", res.to_string()).ok();
+  pub fn update_problemDTOs(storage: &Storage, api: &Api) -> Result<(), RecError> {
+    let (res, source) = api.fetch::<ApiProblemset>("problemset.problems", &[], "fetching problem data...")?;
+    storage.write_cache(&storage.cache_file("problems"), &res, &source)
   }
 
   #[allow(non_snake_case)]
-  pub fn get_problemDTOs() -> Vec<ProblemDTO> {
-    if !Path::new("problems").exists() {
-      update_problemDTOs();
+  pub fn get_problemDTOs(storage: &Storage, api: &Api) -> Result<Vec<ProblemDTO>, RecError> {
+    let file_name = storage.cache_file("problems");
+    if !file_name.exists() {
+      update_problemDTOs(storage, api)?;
     }
-    let res: Value = serde_json::from_str(&fs::read_to_string("problems").expect("read problems")).expect("convert str to json");
+    let res: ApiProblemset = read_cache(&file_name)?;
+    let solved_counts: HashMap<(Option<i64>, &str), i64> = res.problem_statistics.iter()
+      .map(|element| ((element.contest_id, element.index.as_str()), element.solved_count))
+      .collect();
     let mut problemDTOs: Vec<ProblemDTO> = Vec::new();
-    for element in res["result"]["problems"].as_array().unwrap() {
-      if element["rating"].is_null() || element["contestId"].is_null() {
-        continue;
+    for element in &res.problems {
+      if let Some(mut tmp) = element.to_dto() {
+        tmp.solved_count = solved_counts.get(&(element.contest_id, element.index.as_str())).copied();
+        problemDTOs.push(tmp);
       }
-      let tmp = ProblemDTO {
-        contest_id: element["contestId"].as_i64().unwrap(),
-        index: element["index"].as_str().unwrap().to_string(),
-        name: element["name"].as_str().unwrap().to_string(),
-        rating: element["rating"].as_i64().unwrap(),
-      };
-      problemDTOs.push(tmp);
-    } 
+    }
 
-    problemDTOs
+    Ok(problemDTOs)
   }
 
   #[derive(Serialize, Deserialize)]
@@ -55,6 +158,8 @@ pub mod DTOs {
     pub id: i64,
     pub name: String,
     pub contest_type: String,
+    pub gym: bool,
+    pub start_time: Option<i64>,
   }
 
   impl ContestDTO {
@@ -63,38 +168,121 @@ pub mod DTOs {
         id: self.id,
         name: self.name.clone(),
         contest_type: self.contest_type.clone(),
+        gym: self.gym,
+        start_time: self.start_time,
       }
     }
   }
 
   #[allow(non_snake_case)]
-  pub fn update_contestDTOs() {
-    let spin = SpinnerBuilder::new("fetching contest data...".into()).start();
-    sleep(Duration::from_secs(2));
-    let response = reqwest::blocking::get("https://codeforces.com/api/contest.list").unwrap();
-    spin.close();
-    print!("\r                                   \r");
-    let res: Value = response.json().unwrap();
-    fs::write("contests", res.to_string()).ok();
+  pub fn update_contestDTOs(storage: &Storage, api: &Api) -> Result<(), RecError> {
+    let (res, source) = api.fetch::<Vec<ApiContest>>("contest.list", &[], "fetching contest data...")?;
+    storage.write_cache(&storage.cache_file("contests"), &res, &source)
   }
 
   #[allow(non_snake_case)]
-  pub fn get_contestDTOs() -> Vec<ContestDTO> {
-    if !Path::new("contests").exists() {
-      update_contestDTOs();
+  pub fn get_contestDTOs(storage: &Storage, api: &Api) -> Result<Vec<ContestDTO>, RecError> {
+    let file_name = storage.cache_file("contests");
+    if !file_name.exists() {
+      update_contestDTOs(storage, api)?;
     }
-    let res: Value = serde_json::from_str(&fs::read_to_string("contests").expect("read contests")).expect("convert str to json");
+    let res: Vec<ApiContest> = read_cache(&file_name)?;
     let mut contestDTOs: Vec<ContestDTO> = Vec::new();
-    for element in res["result"].as_array().unwrap() {
+    for element in res {
       let tmp = ContestDTO {
-        id: element["id"].as_i64().unwrap(),
-        name: element["name"].as_str().unwrap().to_string(),
-        contest_type: element["type"].as_str().unwrap().to_string(),
+        id: element.id,
+        name: element.name,
+        contest_type: element.contest_type,
+        gym: false,
+        start_time: element.start_time,
       };
       contestDTOs.push(tmp);
     }
 
-    contestDTOs
+    Ok(contestDTOs)
+  }
+
+  //the contest and problems of one gym contest, signed so that group contests the user can see are included
+  fn update_gym_standings(contest_id: i64, storage: &Storage, api: &Api) -> Result<(), RecError> {
+    let (res, source) = api.request("contest.standings").param("contestId", &contest_id.to_string()).sign(api.has_credentials())
+                           .fetch::<ApiStandings>(&format!("fetching gym contest {}...", contest_id))?;
+    storage.write_cache(&storage.gym_file(contest_id), &res, &source)
+  }
+
+  //fetch the standings of the gym_contests from the config, with full the standings already cached are fetched again
+  #[allow(non_snake_case)]
+  pub fn update_gymDTOs(full: bool, storage: &Storage, api: &Api, config: &Config) -> Result<(), RecError> {
+    for contest_id in &config.gym_contests {
+      if full || !storage.gym_file(*contest_id).exists() {
+        update_gym_standings(*contest_id, storage, api)?;
+      }
+    }
+    Ok(())
+  }
+
+  //gym problems have no rating, so it is estimated from the contest's stars and how many teams solved the problem:
+  //the stars give the rating of a typical team, and the problem is rated where such a team solves it at that rate
+  fn estimate_difficulty(stars: Option<i64>, solved: usize, teams: usize) -> i64 {
+    let typical = match stars {
+      Some(stars) => 800 + 400 * stars.clamp(1, 5),
+      None => 1900,
+    };
+    let solve_rate = (solved as f64 + 1.0) / (teams as f64 + 2.0);
+    let rating = typical as f64 + 400.0 * ((1.0 - solve_rate) / solve_rate).log10();
+    ((rating / 100.0).round() as i64 * 100).clamp(800, 3500)
+  }
+
+  //the standings of a gym contest, fetched when they aren't cached yet or were cached without the contest
+  fn get_gym_standings(contest_id: i64, storage: &Storage, api: &Api) -> Result<(ApiContest, ApiStandings), RecError> {
+    let standings_file = storage.gym_file(contest_id);
+    if !standings_file.exists() {
+      update_gym_standings(contest_id, storage, api)?;
+    }
+    let mut standings: ApiStandings = read_cache(&standings_file)?;
+    if standings.contest.is_none() {
+      update_gym_standings(contest_id, storage, api)?;
+      standings = read_cache(&standings_file)?;
+    }
+    let contest = standings.contest.take().ok_or_else(|| RecError::json(&standings_file.display().to_string(), "missing field `contest`"))?;
+    Ok((contest, standings))
+  }
+
+  //the gym contests and problems of the gym_contests from the config, the contest comes with its standings so
+  //group and private contests missing from the public gym list are found too. A contest that can't be loaded, e.g. a
+  //private one without api_key or a mistyped id, is skipped with a warning rather than failing every command
+  #[allow(non_snake_case)]
+  pub fn get_gymDTOs(storage: &Storage, api: &Api, config: &Config) -> (Vec<ContestDTO>, Vec<ProblemDTO>) {
+    let mut contestDTOs: Vec<ContestDTO> = Vec::new();
+    let mut problemDTOs: Vec<ProblemDTO> = Vec::new();
+    for contest_id in &config.gym_contests {
+      let (element, standings) = match get_gym_standings(*contest_id, storage, api) {
+        Ok(res) => res,
+        Err(error) => {
+          println!("Skipping gym contest {}: {}", contest_id, error);
+          continue;
+        },
+      };
+      for (i, problem) in standings.problems.iter().enumerate() {
+        let solved = standings.rows.iter().filter(|row| row.problem_results.get(i).is_some_and(|res| res.points > 0.0)).count();
+        problemDTOs.push(ProblemDTO {
+          contest_id: element.id,
+          index: problem.index.clone(),
+          name: problem.name.clone(),
+          rating: problem.rating.unwrap_or_else(|| estimate_difficulty(element.difficulty, solved, standings.rows.len())),
+          tags: problem.tags.clone(),
+          solved_count: Some(solved as i64),
+        });
+      }
+      contestDTOs.push(ContestDTO {
+        id: element.id,
+        name: element.name,
+        contest_type: element.contest_type,
+        gym: true,
+        start_time: element.start_time,
+      });
+    }
+
+    (contestDTOs, problemDTOs)
   }
 
   pub struct SubmissionDTO {
@@ -102,80 +290,284 @@ pub mod DTOs {
     pub verdict: String,
   }
 
+  //submissions fetched per request when syncing, user.status lists the newest submissions first
+  const SYNC_PAGE_SIZE: usize = 100;
+
+  fn submission_id(submission: &Value) -> Option<i64> {
+    submission.get("id").and_then(|id| id.as_i64())
+  }
+
+  //a submission without a verdict yet is fetched again by the next sync
+  fn is_judged(submission: &Value) -> bool {
+    matches!(submission.get("verdict").and_then(|verdict| verdict.as_str()), Some(verdict) if verdict != "TESTING")
+  }
+
+  //the stored submissions, if all of them can be told apart by id
+  fn known_submissions(path: &Path) -> Option<Vec<Value>> {
+    let submissions: Vec<Value> = read_cache(path).ok()?;
+    submissions.iter().all(|submission| submission_id(submission).is_some()).then_some(submissions)
+  }
+
+  //where a sync can stop in a page: at the first stored submission that is judged and older than the oldest stored one
+  //still being judged, since a judged submission can be newer than one still being judged, ids grow with the time
+  fn stop_index(known: &[Value], page: &[Value]) -> Option<usize> {
+    let judged_ids: HashSet<i64> = known.iter().filter(|submission| is_judged(submission)).filter_map(submission_id).collect();
+    let oldest_pending = known.iter().filter(|submission| !is_judged(submission)).filter_map(submission_id).min();
+    page.iter().position(|submission| {
+      submission_id(submission).is_some_and(|id| judged_ids.contains(&id) && oldest_pending.is_none_or(|pending| id < pending))
+    })
+  }
+
+  //the fetched pages up to the stop, newest first, followed by the stored submissions that weren't fetched again,
+  //and how many weren't stored before. A submission made during the sync shifts the pages, so the same one can be
+  //fetched twice. None when the pages hold nothing before the stop
+  fn merge_pages(known: Vec<Value>, pages: &[Vec<Value>]) -> Option<(Vec<Value>, usize)> {
+    let mut fetched: Vec<Value> = Vec::new();
+    let mut fetched_ids: HashSet<i64> = HashSet::new();
+    for page in pages {
+      let stop = stop_index(&known, page).unwrap_or(page.len());
+      for submission in &page[..stop] {
+        if submission_id(submission).is_none_or(|id| fetched_ids.insert(id)) {
+          fetched.push(submission.clone());
+        }
+      }
+      if stop < page.len() {
+        break;
+      }
+    }
+    if fetched.is_empty() {
+      return None;
+    }
+    let known_ids: HashSet<i64> = known.iter().filter_map(submission_id).collect();
+    let added = fetched.iter().filter(|submission| submission_id(submission).is_none_or(|id| !known_ids.contains(&id))).count();
+    fetched.extend(known.into_iter().filter(|submission| submission_id(submission).is_some_and(|id| !fetched_ids.contains(&id))));
+    Some((fetched, added))
+  }
+
+  fn result_list(res: &Value) -> Vec<Value> {
+    match res.get("result") {
+      Some(Value::Array(list)) => list.clone(),
+      _ => Vec::new(),
+    }
+  }
+
+  //only fetch the submissions newer than the stored ones unless full is set, or nothing usable is stored yet
+  #[allow(non_snake_case)]
+  pub fn update_submissionDTOs(handle: &str, full: bool, storage: &Storage, api: &Api) -> Result<(), RecError> {
+    let file_name = storage.submissions_file(handle);
+    let known = if full { None } else { known_submissions(&file_name) };
+    let Some(known) = known else {
+      let (res, source) = api.request("user.status").param("handle", handle).sign(api.has_credentials())
+                             .fetch::<Vec<ApiSubmission>>("fetching submission data...")?;
+      let (submissions, _) = merge_pages(Vec::new(), &[result_list(&res)]).unwrap_or_default();
+      return storage.write_cache(&file_name, &json!({"status": "OK", "result": submissions}), &source);
+    };
+
+    let mut pages: Vec<Vec<Value>> = Vec::new();
+    let mut from = 1;
+    let mut source;
+    loop {
+      let (res, page_source) = api.request("user.status").param("handle", handle)
+                                  .param("from", &from.to_string()).param("count", &SYNC_PAGE_SIZE.to_string())
+                                  .sign(api.has_credentials())
+                                  .fetch::<Vec<ApiSubmission>>("fetching new submissions...")?;
+      source = page_source;
+      let page = result_list(&res);
+      let done = page.len() < SYNC_PAGE_SIZE || stop_index(&known, &page).is_some();
+      pages.push(page);
+      if done {
+        break;
+      }
+      from += SYNC_PAGE_SIZE;
+    }
+
+    let Some((submissions, added)) = merge_pages(known, &pages) else {
+      return storage.record_fetch(&file_name, &source);
+    };
+    storage.write_cache(&file_name, &json!({"status": "OK", "result": submissions}), &source)?;
+    if added > 0 {
+      println!("Added {} new submissions.", added);
+    }
+    Ok(())
+  }
+
+  //a submission as watch reports it, fetched without touching the cache
+  pub struct VerdictDTO {
+    pub id: i64,
+    pub contest_id: i64,
+    pub index: String,
+    pub time: i64,
+    //none while the submission waits in the queue
+    pub verdict: Option<String>,
+    pub passed_test_count: i64,
+  }
+
+  //the count newest submissions of the handle, newest first
   #[allow(non_snake_case)]
-  pub fn update_submissionDTOs(handle: &String) {
-    let spin = SpinnerBuilder::new("fetching submission data...".into()).start();
-    sleep(Duration::from_secs(2));
-    let response = reqwest::blocking::get("https://codeforces.com/api/user.status?handle=".to_owned() + &handle).unwrap();
-    spin.close();
-    print!("\r                                   \r");
-    let res: Value = response.json().unwrap();  
-    fs::write(&handle, res.to_string()).ok();
+  pub fn get_verdictDTOs(handle: &str, count: usize, api: &Api) -> Result<Vec<VerdictDTO>, RecError> {
+    let (res, source) = api.request("user.status").param("handle", handle)
+                           .param("from", "1").param("count", &count.to_string())
+                           .sign(api.has_credentials())
+                           .fetch::<Vec<ApiSubmission>>("checking submissions...")?;
+    let envelope: ApiResponse<Vec<ApiSubmission>> = serde_json::from_value(res).map_err(|error| RecError::json(&source, error))?;
+    let mut verdictDTOs: Vec<VerdictDTO> = Vec::new();
+    for element in envelope.into_result(&source)? {
+      let (Some(id), Some(contest_id)) = (element.id, element.problem.contest_id) else {
+        continue;
+      };
+      verdictDTOs.push(VerdictDTO {
+        id,
+        contest_id,
+        index: element.problem.index,
+        time: element.creation_time_seconds.unwrap_or(0),
+        verdict: element.verdict.filter(|verdict| verdict != "TESTING"),
+        passed_test_count: element.passed_test_count,
+      });
+    }
+
+    Ok(verdictDTOs)
   }
 
   #[allow(non_snake_case)]
-  pub fn get_submissionDTOs(handle: &String) -> Vec<SubmissionDTO> {
-    if !Path::new(handle).exists() {
-      update_submissionDTOs(&handle);
+  pub fn get_submissionDTOs(handle: &str, storage: &Storage, api: &Api) -> Result<Vec<SubmissionDTO>, RecError> {
+    let file_name = storage.submissions_file(handle);
+    if !file_name.exists() {
+      update_submissionDTOs(handle, true, storage, api)?;
     }
-    let res: Value = serde_json::from_str(&fs::read_to_string(handle).expect("read submissions")).expect("convert str to json");
+    let res: Vec<ApiSubmission> = read_cache(&file_name)?;
     let mut submissionDTOs: Vec<SubmissionDTO> = Vec::new();
-    for element in res["result"].as_array().unwrap() {
-      if element["problem"]["rating"].is_null() || element["problem"]["contestId"].is_null() {
-        continue;
+    for element in res {
+      let mut problem = element.problem;
+      //gym problems have no rating, they are kept with a rating of 0 so that their verdicts still count
+      if problem.contest_id.is_some_and(|id| id >= GYM_CONTEST_ID) {
+        problem.rating = problem.rating.or(Some(0));
       }
-      let prob = ProblemDTO {
-        contest_id: element["problem"]["contestId"].as_i64().unwrap(),
-        index: element["problem"]["index"].as_str().unwrap().to_string(),
-        name: element["problem"]["name"].as_str().unwrap().to_string(),
-        rating: element["problem"]["rating"].as_i64().unwrap(),
+      let Some(prob) = problem.to_dto() else {
+        continue;
       };
+      //submissions still in the queue have no verdict yet
       let tmp = SubmissionDTO {
         problem: prob,
-        verdict: element["verdict"].as_str().unwrap().to_string(),
+        verdict: element.verdict.unwrap_or_else(|| String::from("TESTING")),
       };
       submissionDTOs.push(tmp);
-    } 
+    }
 
-    submissionDTOs
+    Ok(submissionDTOs)
   }
 
   #[allow(dead_code)]
   pub struct UserInfoDTO {
     pub handle: String,
-    pub rank: String, 
+    pub rank: String,
     pub rating: i64,
     pub max_rank: String,
     pub max_rating: i64,
   }
 
   impl UserInfoDTO {
-    pub fn update(handle: &String) {
-      let file_name = "user_info";
-      let spin = SpinnerBuilder::new("fetching userInfo data...".into()).start();
-      sleep(Duration::from_secs(2));
-      let response = reqwest::blocking::get("https://codeforces.com/api/user.info?handles=".to_owned() + &handle).unwrap();
-      spin.close();
-      print!("\r                                   \r");
-      let res: Value = response.json().unwrap();  
-      fs::write(&file_name, res.to_string()).ok();
-    }
-    pub fn new(handle: &String) -> UserInfoDTO {
-      let file_name = "user_info";
-      if !Path::new(&file_name).exists() {
-        Self::update(&handle);
+    pub fn update(handle: &str, storage: &Storage, api: &Api) -> Result<(), RecError> {
+      let (res, source) = api.request("user.info").param("handles", handle).sign(api.has_credentials())
+                             .fetch::<Vec<ApiUser>>("fetching userInfo data...")?;
+      storage.write_cache(&storage.user_info_file(handle), &res, &source)
+    }
+    pub fn new(handle: &str, storage: &Storage, api: &Api) -> Result<UserInfoDTO, RecError> {
+      let file_name = storage.user_info_file(handle);
+      if !file_name.exists() {
+        Self::update(handle, storage, api)?;
       }
-      let res: Value = serde_json::from_str(&fs::read_to_string(&file_name).expect("read user info")).expect("convert str to json");
-      
+      let res: Vec<ApiUser> = read_cache(&file_name)?;
+      let info = res.into_iter().next().ok_or_else(|| RecError::json("user_info", "no user in the result"))?;
+      let (Some(rank), Some(rating), Some(max_rank), Some(max_rating)) = (info.rank, info.rating, info.max_rank, info.max_rating) else {
+        return Err(RecError::json("user_info", format!("{} has no rating yet, take part in a rated contest first", info.handle)));
+      };
+
       let user_infoDTO = UserInfoDTO {
-        handle: handle.clone(),
-        rank: res["result"][0]["rank"].as_str().unwrap().to_string(),
-        rating: res["result"][0]["rating"].as_i64().unwrap(),
-        max_rank: res["result"][0]["maxRank"].as_str().unwrap().to_string(),
-        max_rating: res["result"][0]["maxRating"].as_i64().unwrap(),
+        handle: handle.to_string(),
+        rank,
+        rating,
+        max_rank,
+        max_rating,
       };
 
-      user_infoDTO
+      Ok(user_infoDTO)
+    }
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use super::*;
+
+    fn submission(id: i64, verdict: Option<&str>) -> Value {
+      match verdict {
+        Some(verdict) => json!({"id": id, "verdict": verdict}),
+        None => json!({"id": id}),
+      }
+    }
+
+    fn judged(ids: &[i64]) -> Vec<Value> {
+      ids.iter().map(|id| submission(*id, Some("OK"))).collect()
+    }
+
+    fn summary(submissions: &[Value]) -> Vec<(i64, bool)> {
+      submissions.iter().map(|submission| (submission_id(submission).unwrap(), is_judged(submission))).collect()
+    }
+
+    #[test]
+    fn new_submissions_go_first() {
+      let known = judged(&[3, 2, 1]);
+      let pages = [judged(&[5, 4, 3, 2, 1])];
+      assert_eq!(stop_index(&known, &pages[0]), Some(2));
+      let (merged, added) = merge_pages(known, &pages).unwrap();
+      assert_eq!(summary(&merged), [(5, true), (4, true), (3, true), (2, true), (1, true)]);
+      assert_eq!(added, 2);
+    }
+
+    #[test]
+    fn nothing_new() {
+      assert!(merge_pages(judged(&[3, 2, 1]), &[judged(&[3, 2, 1])]).is_none());
+    }
+
+    //a submission stored while it was being judged is replaced by its verdict
+    #[test]
+    fn pending_submission_gets_judged() {
+      let known = vec![submission(3, None), submission(2, Some("OK")), submission(1, Some("OK"))];
+      let pages = [judged(&[4, 3, 2, 1])];
+      assert_eq!(stop_index(&known, &pages[0]), Some(2));
+      let (merged, added) = merge_pages(known, &pages).unwrap();
+      assert_eq!(summary(&merged), [(4, true), (3, true), (2, true), (1, true)]);
+      assert_eq!(added, 1);
+    }
+
+    //the judged submissions 5 and 6 don't stop the sync before it gets past the pending 4
+    #[test]
+    fn judged_submission_newer_than_a_pending_one() {
+      let known = vec![submission(5, Some("OK")), submission(4, Some("TESTING")), submission(3, Some("OK"))];
+      let pages = [judged(&[6, 5]), judged(&[4, 3])];
+      assert_eq!(stop_index(&known, &pages[0]), None);
+      assert_eq!(stop_index(&known, &pages[1]), Some(1));
+      let (merged, added) = merge_pages(known, &pages).unwrap();
+      assert_eq!(summary(&merged), [(6, true), (5, true), (4, true), (3, true)]);
+      assert_eq!(added, 1);
+    }
+
+    //a submission made during the sync pushes 5 onto the second page as well
+    #[test]
+    fn shifted_pages_are_deduped() {
+      let known = judged(&[3]);
+      let pages = [judged(&[7, 6, 5]), judged(&[5, 4, 3])];
+      let (merged, added) = merge_pages(known, &pages).unwrap();
+      assert_eq!(summary(&merged), [(7, true), (6, true), (5, true), (4, true), (3, true)]);
+      assert_eq!(added, 4);
+    }
+
+    //update --full merges into nothing, so submissions that are gone from codeforces are dropped
+    #[test]
+    fn full_sync_replaces_everything() {
+      let pages = [vec![submission(4, None), submission(2, Some("OK")), submission(1, Some("WRONG_ANSWER"))]];
+      let (merged, added) = merge_pages(Vec::new(), &pages).unwrap();
+      assert_eq!(summary(&merged), [(4, false), (2, true), (1, true)]);
+      assert_eq!(added, 3);
     }
   }
 }
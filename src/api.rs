@@ -0,0 +1,233 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+use std::thread::sleep;
+use rand::Rng;
+use rand::distributions::Alphanumeric;
+use reqwest::blocking::Client;
+use sha2::{Digest, Sha512};
+use serde_json::Value;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use spinner::SpinnerBuilder;
+use crate::config::Config;
+use crate::error::RecError;
+use crate::history;
+
+//every codeforces API method answers with {"status": "OK" | "FAILED", "comment": ..., "result": ...}
+#[derive(Serialize, Deserialize)]
+pub struct ApiResponse<T> {
+  pub status: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub comment: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub result: Option<T>,
+}
+
+impl<T> ApiResponse<T> {
+  pub fn into_result(self, source: &str) -> Result<T, RecError> {
+    if self.status != "OK" {
+      let comment = self.comment.unwrap_or_else(|| String::from("status ") + &self.status);
+      return Err(RecError::ApiFailed(source.to_string(), comment));
+    }
+    self.result.ok_or_else(|| RecError::json(source, "missing field `result`"))
+  }
+}
+
+//codeforces allows one call every two seconds
+const CALL_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct Api {
+  //the configured base url first, then the mirrors in the order they should be tried
+  base_urls: Vec<String>,
+  pub site_base_url: String,
+  client: Client,
+  max_attempts: u32,
+  last_call: Cell<Option<Instant>>,
+  //api_key and api_secret, needed for signed requests
+  credentials: Option<(String, String)>,
+}
+
+//one API call, built with Api::request
+pub struct Request<'a> {
+  api: &'a Api,
+  method: String,
+  params: Vec<(String, String)>,
+  signed: bool,
+}
+
+impl Api {
+  pub fn new(config: &Config) -> Api {
+    let mut base_urls = vec![config.api_base_url.trim_end_matches('/').to_string()];
+    for mirror in &config.api_mirrors {
+      base_urls.push(mirror.trim_end_matches('/').to_string());
+    }
+    Api {
+      base_urls,
+      site_base_url: config.site_base_url.trim_end_matches('/').to_string(),
+      client: Client::builder().timeout(Duration::from_secs(config.api_timeout_secs)).build().expect("build http client"),
+      max_attempts: config.api_max_attempts.max(1),
+      last_call: Cell::new(None),
+      credentials: config.api_key.clone().zip(config.api_secret.clone()),
+    }
+  }
+
+  pub fn request(&self, method: &str) -> Request<'_> {
+    Request {
+      api: self,
+      method: method.to_string(),
+      params: Vec::new(),
+      signed: false,
+    }
+  }
+
+  pub fn has_credentials(&self) -> bool {
+    self.credentials.is_some()
+  }
+
+  pub fn fetch<T: DeserializeOwned>(&self, method: &str, params: &[(&str, &str)], message: &str) -> Result<(Value, String), RecError> {
+    params.iter().fold(self.request(method), |request, (name, value)| request.param(name, value)).fetch::<T>(message)
+  }
+
+  //only wait for what's left of the interval since the previous call
+  fn wait_turn(&self) {
+    if let Some(last_call) = self.last_call.get() {
+      let elapsed = last_call.elapsed();
+      if elapsed < CALL_INTERVAL {
+        sleep(CALL_INTERVAL - elapsed);
+      }
+    }
+    self.last_call.set(Some(Instant::now()));
+  }
+}
+
+impl Request<'_> {
+  pub fn param(mut self, name: &str, value: &str) -> Self {
+    self.params.push((name.to_string(), value.to_string()));
+    self
+  }
+
+  //sign the request with the api key from the config, for the methods that need authorization
+  pub fn sign(mut self, signed: bool) -> Self {
+    self.signed = signed;
+    self
+  }
+
+  //the response is checked against T before it is returned, so a failed or malformed reply never reaches the cache,
+  //it comes with the url of the method that answered
+  pub fn fetch<T: DeserializeOwned>(self, message: &str) -> Result<(Value, String), RecError> {
+    if self.signed && self.api.credentials.is_none() {
+      return Err(RecError::Config(format!("{} needs api_key and api_secret to be set", self.method)));
+    }
+    let spin = SpinnerBuilder::new(message.into()).start();
+    let base_urls = &self.api.base_urls;
+    let mut res = Err(RecError::Config(String::from("no API base url configured")));
+    for (i, base_url) in base_urls.iter().enumerate() {
+      let url = base_url.clone() + "/" + &self.method;
+      //the key and secret only go to api_base_url, the mirrors get the same request unsigned
+      res = self.fetch_with_retry::<T>(&url, self.signed && i == 0).map(|value| (value, url));
+      match &res {
+        //a FAILED status is codeforces' own answer, another mirror won't answer differently
+        Ok(_) | Err(RecError::ApiFailed(..)) => break,
+        Err(error) if i + 1 < base_urls.len() => eprintln!("\r{}, trying {}", error, base_urls[i + 1]),
+        Err(_) => (),
+      }
+    }
+    spin.close();
+    print!("\r                                   \r");
+
+    res
+  }
+
+  fn fetch_with_retry<T: DeserializeOwned>(&self, url: &str, signed: bool) -> Result<Value, RecError> {
+    let mut backoff = CALL_INTERVAL;
+    let mut attempt = 1;
+    loop {
+      self.api.wait_turn();
+      let res = self.fetch_from::<T>(url, signed);
+      match &res {
+        Err(error) if error.is_transient() && attempt < self.api.max_attempts => {
+          eprintln!("\r{}, retrying in {}s", error, backoff.as_secs());
+          sleep(backoff);
+          backoff *= 2;
+          attempt += 1;
+        },
+        _ => return res,
+      }
+    }
+  }
+
+  //apiKey, time and apiSig as described in https://codeforces.com/apiHelp, signed again for every attempt
+  fn signed_params(&self, key: &str, secret: &str) -> Vec<(String, String)> {
+    let prefix: String = rand::thread_rng().sample_iter(&Alphanumeric).take(6).map(char::from).collect();
+    sign_params(&self.method, &self.params, key, secret, history::now(), &prefix)
+  }
+
+  fn fetch_from<T: DeserializeOwned>(&self, url: &str, signed: bool) -> Result<Value, RecError> {
+    let mut url = reqwest::Url::parse(url).map_err(|error| RecError::json(url, error))?;
+    if !self.params.is_empty() {
+      url.query_pairs_mut().extend_pairs(&self.params);
+    }
+    //errors show the url without the key and the signature
+    let url_str = url.to_string();
+    if let (true, Some((key, secret))) = (signed, &self.api.credentials) {
+      url.set_query(None);
+      url.query_pairs_mut().extend_pairs(self.signed_params(key, secret));
+    }
+    let response = self.api.client.get(url).send().map_err(|error| RecError::Network(url_str.clone(), error))?;
+    let status = response.status();
+    let body = response.text().map_err(|error| RecError::Network(url_str.clone(), error))?;
+    //codeforces also answers errors like an unknown handle with a 400 and a json envelope
+    let res: Value = match serde_json::from_str(&body) {
+      Ok(res) => res,
+      Err(_) if !status.is_success() => return Err(RecError::HttpStatus(url_str, status.as_u16())),
+      Err(error) => return Err(RecError::json(&url_str, error)),
+    };
+    let envelope: ApiResponse<T> = serde_json::from_value(res.clone()).map_err(|error| RecError::json(&url_str, error))?;
+    envelope.into_result(&url_str)?;
+    if !status.is_success() {
+      return Err(RecError::HttpStatus(url_str, status.as_u16()));
+    }
+
+    Ok(res)
+  }
+}
+
+//the params of a call with apiKey, time and apiSig added, prefix is the random part in front of the hash
+fn sign_params(method: &str, params: &[(String, String)], key: &str, secret: &str, time: i64, prefix: &str) -> Vec<(String, String)> {
+  let mut params = params.to_vec();
+  params.push((String::from("apiKey"), key.to_string()));
+  params.push((String::from("time"), time.to_string()));
+  params.sort();
+  let query: Vec<String> = params.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+  let hash = Sha512::digest(format!("{}/{}?{}#{}", prefix, method, query.join("&"), secret));
+  params.push((String::from("apiSig"), format!("{}{:x}", prefix, hash)));
+  params
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn params(list: &[(&str, &str)]) -> Vec<(String, String)> {
+    list.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+  }
+
+  //the example of https://codeforces.com/apiHelp
+  #[test]
+  fn sign_params_matches_api_help_example() {
+    let signed = sign_params("contest.hacks", &params(&[("contestId", "566")]), "xxx", "yyy", 1234567890, "123456");
+    assert_eq!(signed, params(&[
+      ("apiKey", "xxx"),
+      ("contestId", "566"),
+      ("time", "1234567890"),
+      ("apiSig", "1234567f467d1cd837599d2f0dc9fd8beec8fad80ee7d02f0b65ad153a963bca2923de885e11c96cba96beceaba6dd7433d20c0cbb507b7615b3dccfb693b6163ccc94"),
+    ]));
+  }
+
+  #[test]
+  fn sign_params_sorts_by_name_then_value() {
+    let signed = sign_params("user.status", &params(&[("handle", "b"), ("count", "10"), ("handle", "a")]), "k", "s", 1, "000000");
+    let names: Vec<&str> = signed.iter().map(|(name, value)| if name == "handle" { value.as_str() } else { name.as_str() }).collect();
+    assert_eq!(names, ["apiKey", "count", "a", "b", "time", "apiSig"]);
+  }
+}
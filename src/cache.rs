@@ -0,0 +1,134 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde_json::Value;
+use crate::config::Config;
+use crate::error::RecError;
+use crate::history::format_duration;
+use crate::storage::{Storage, read_json};
+
+pub const DATASETS: [&str; 5] = ["problems", "contests", "submissions", "user_info", "gym"];
+
+//the cache file of a dataset, submissions and user_info are kept per handle
+pub fn dataset_file(storage: &Storage, dataset: &str, handle: &str) -> PathBuf {
+  match dataset {
+    "submissions" => storage.submissions_file(handle),
+    "user_info" => storage.user_info_file(handle),
+    _ => storage.cache_file(dataset),
+  }
+}
+
+//a missing cache is always stale
+pub fn is_stale(storage: &Storage, config: &Config, dataset: &str, handle: &str) -> bool {
+  match storage.cache_age(&dataset_file(storage, dataset, handle)) {
+    Some(age) => age > config.cache_ttl(dataset),
+    None => true,
+  }
+}
+
+pub fn warn_stale(storage: &Storage, config: &Config, handle: &str) {
+  //the standings of a gym contest hardly change once it is over, they are only fetched once
+  for dataset in DATASETS.into_iter().filter(|dataset| *dataset != "gym") {
+    let age = storage.cache_age(&dataset_file(storage, dataset, handle));
+    if let Some(age) = age.filter(|age| *age > config.cache_ttl(dataset)) {
+      println!("The cached {} data is {} old, run \"rec update --if-stale\" to refresh it.", dataset, format_duration(age));
+    }
+  }
+}
+
+//the number of problems, contests, submissions or users in a cache file
+fn record_count(path: &Path) -> Option<usize> {
+  let content: Value = read_json(path).ok()?;
+  match content.get("result")? {
+    Value::Array(list) => Some(list.len()),
+    result => result.get("problems")?.as_array().map(|list| list.len()),
+  }
+}
+
+fn format_size(bytes: u64) -> String {
+  if bytes >= 1 << 20 {
+    format!("{:.1} MB", bytes as f64 / (1 << 20) as f64)
+  } else {
+    format!("{:.1} KB", bytes as f64 / (1 << 10) as f64)
+  }
+}
+
+//every cache file with its dataset, the per handle datasets list each handle
+fn cache_files(storage: &Storage) -> Vec<(String, PathBuf)> {
+  let mut res: Vec<(String, PathBuf)> = Vec::new();
+  for dataset in DATASETS {
+    let path = storage.cache_dir().join(dataset);
+    if path.is_dir() {
+      let mut names: Vec<String> = fs::read_dir(&path).into_iter().flatten().flatten()
+                                      .map(|entry| entry.file_name().to_string_lossy().to_string())
+                                      .filter(|name| !name.ends_with(".tmp"))
+                                      .collect();
+      names.sort();
+      res.extend(names.into_iter().map(|name| (dataset.to_string() + "/" + &name, path.join(name))));
+    } else if path.is_file() {
+      res.push((dataset.to_string(), path));
+    }
+  }
+
+  res
+}
+
+fn print_status(storage: &Storage, config: &Config) {
+  println!();
+  println!("|         dataset          |        age        |   size   | records |  source");
+  for (name, path) in cache_files(storage) {
+    let dataset = name.split('/').next().unwrap_or("");
+    let meta = storage.cache_meta(&path);
+    let age = match storage.cache_age(&path) {
+      Some(age) if age > config.cache_ttl(dataset) => format_duration(age) + " (stale)",
+      Some(age) => format_duration(age),
+      None => String::from("-"),
+    };
+    let size = fs::metadata(&path).map(|metadata| format_size(metadata.len())).unwrap_or_else(|_| String::from("-"));
+    let records = record_count(&path).map(|count| count.to_string()).unwrap_or_else(|| String::from("-"));
+    let source = meta.map(|meta| meta.source).unwrap_or_else(|| String::from("-"));
+    println!("|{:^26}|{:^19}|{:^10}|{:^9}| {}", name, age, size, records, source);
+  }
+  println!();
+}
+
+pub fn cache_command(args: &[String], storage: &Storage, config: &Config) -> Result<(), RecError> {
+  match args.get(2).map(|cmd| cmd.as_str()) {
+    Some("status") | None => print_status(storage, config),
+    _ => return Err(RecError::Usage(String::from("Usage: rec cache [status]"))),
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeMap;
+  use crate::storage::{CacheMeta, write_json};
+  use super::*;
+
+  #[test]
+  fn cache_ttl_falls_back_to_the_default() {
+    let mut config = Config::default();
+    config.cache_ttl_hours.clear();
+    config.cache_ttl_hours.insert(String::from("contests"), 1);
+    assert_eq!(config.cache_ttl("contests"), 60 * 60);
+    assert_eq!(config.cache_ttl("problems"), 72 * 60 * 60);
+    assert_eq!(config.cache_ttl("no_such_dataset"), 24 * 60 * 60);
+  }
+
+  #[test]
+  fn is_stale_by_fetch_time() {
+    let dir = std::env::temp_dir().join(format!("rec-cache-test-{}", std::process::id()));
+    let storage = Storage::new(Some(dir.clone()), "default");
+    let config = Config::default();
+    assert!(is_stale(&storage, &config, "problems", "tourist"));
+
+    storage.write_cache(&storage.cache_file("problems"), &Vec::<i64>::new(), "test").unwrap();
+    assert!(!is_stale(&storage, &config, "problems", "tourist"));
+
+    let fetched_at = crate::history::now() - config.cache_ttl("problems") - 60;
+    let metadata = BTreeMap::from([(String::from("problems"), CacheMeta { fetched_at, source: String::from("test") })]);
+    write_json(&storage.cache_file("metadata"), &metadata).unwrap();
+    assert!(is_stale(&storage, &config, "problems", "tourist"));
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
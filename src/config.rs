@@ -0,0 +1,275 @@
+use std::{cmp, env, io};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use serde_json::Value;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use crate::problem::{Division, Popularity};
+use crate::error::RecError;
+use crate::rating::RatingModel;
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Config {
+  //used by every profile that doesn't set its own handle
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub handle: Option<String>,
+  #[serde(default = "default_profile")]
+  pub profile: String,
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub profiles: BTreeMap<String, ProfileConfig>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub data_dir: Option<PathBuf>,
+  #[serde(default = "default_api_base_url")]
+  pub api_base_url: String,
+  #[serde(default = "default_site_base_url")]
+  pub site_base_url: String,
+  //tried in order when api_base_url can't be reached, requests to them are never signed so the api key and secret
+  //only go to api_base_url
+  #[serde(default)]
+  pub api_mirrors: Vec<String>,
+  //tries per base url for timeouts, server errors and "Call limit exceeded", waiting twice as long each time
+  #[serde(default = "default_api_max_attempts")]
+  pub api_max_attempts: u32,
+  #[serde(default = "default_api_timeout_secs")]
+  pub api_timeout_secs: u64,
+  //from https://codeforces.com/settings/api, the config file is only readable by its owner once they are set
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub api_key: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub api_secret: Option<String>,
+  //bind, and query without -old or -since, only pick problems from contests of the last recent_years years
+  #[serde(default = "default_recent_years")]
+  pub recent_years: i64,
+  //bind only picks problems with at least one whitelisted tag and no blacklisted tag
+  #[serde(default)]
+  pub tag_whitelist: Vec<String>,
+  #[serde(default)]
+  pub tag_blacklist: Vec<String>,
+  //"avoid_obscure" or "prefer_obscure" makes bind skip or favour problems with fewer than obscure_solved_count solvers
+  #[serde(default)]
+  pub bind_popularity: Popularity,
+  #[serde(default = "default_obscure_solved_count")]
+  pub obscure_solved_count: i64,
+  //coefficients of the factors bind weighs its candidates by, 0 turns a factor off, see selection::weigh
+  #[serde(default = "default_bind_weight_distance")]
+  pub bind_weight_distance: f64,
+  #[serde(default = "default_bind_weight_recency")]
+  pub bind_weight_recency: f64,
+  #[serde(default = "default_bind_weight_popularity")]
+  pub bind_weight_popularity: f64,
+  #[serde(default = "default_bind_weight_division")]
+  pub bind_weight_division: f64,
+  //how much bind likes each division, e.g. {"Div1": 1.0, "GlobalRound": 0.5}, missing divisions count 1.0
+  #[serde(default)]
+  pub bind_division_preference: BTreeMap<Division, f64>,
+  //gym contest ids whose problems are fetched with contest.standings and can be queried with -gym
+  #[serde(default)]
+  pub gym_contests: Vec<i64>,
+  //scale the score of a solve by its solving time against time_budget, see Config::time_budget
+  #[serde(default)]
+  pub time_aware_rating: bool,
+  //minutes for an 800 problem, and the extra minutes for every 100 rating above it
+  #[serde(default = "default_time_budget_base")]
+  pub time_budget_base: i64,
+  #[serde(default = "default_time_budget_step")]
+  pub time_budget_step: i64,
+  //hours before a cached dataset counts as stale, for problems, contests, submissions and user_info
+  #[serde(default = "default_cache_ttl_hours")]
+  pub cache_ttl_hours: BTreeMap<String, i64>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct ProfileConfig {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub handle: Option<String>,
+  //how solved and unsolved problems move the recommended difficulty, "elo" or "glicko2"
+  #[serde(default)]
+  pub rating_model: RatingModel,
+}
+
+fn default_profile() -> String {
+  String::from(DEFAULT_PROFILE)
+}
+
+fn default_api_base_url() -> String {
+  String::from("https://codeforces.com/api")
+}
+
+fn default_site_base_url() -> String {
+  String::from("https://codeforces.com")
+}
+
+fn default_recent_years() -> i64 {
+  2
+}
+
+fn default_obscure_solved_count() -> i64 {
+  500
+}
+
+fn default_bind_weight_distance() -> f64 {
+  1.0
+}
+
+fn default_bind_weight_recency() -> f64 {
+  0.5
+}
+
+fn default_bind_weight_popularity() -> f64 {
+  0.5
+}
+
+fn default_bind_weight_division() -> f64 {
+  1.0
+}
+
+fn default_api_max_attempts() -> u32 {
+  3
+}
+
+fn default_api_timeout_secs() -> u64 {
+  60
+}
+
+fn default_time_budget_base() -> i64 {
+  30
+}
+
+fn default_time_budget_step() -> i64 {
+  10
+}
+
+fn default_cache_ttl_hours() -> BTreeMap<String, i64> {
+  BTreeMap::from([
+    (String::from("problems"), 72),
+    (String::from("contests"), 72),
+    (String::from("submissions"), 24),
+    (String::from("user_info"), 168),
+  ])
+}
+
+impl Default for Config {
+  fn default() -> Config {
+    serde_json::from_str("{}").unwrap()
+  }
+}
+
+impl Config {
+  pub fn path() -> PathBuf {
+    dirs::config_dir().unwrap_or_else(|| PathBuf::from(".")).join("rec").join("config")
+  }
+
+  //a missing config file is the default config, an unreadable or malformed one is an error
+  pub fn load() -> Result<Config, RecError> {
+    let path = Self::path();
+    match fs::read_to_string(&path) {
+      Ok(content) => serde_json::from_str(&content).map_err(|error| RecError::json(&path.display().to_string(), error)),
+      Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+      Err(error) => Err(RecError::Io(path, error)),
+    }
+  }
+
+  pub fn save(&self) -> Result<(), RecError> {
+    let path = Self::path();
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent).map_err(|error| RecError::Io(parent.to_path_buf(), error))?;
+    }
+    //the secret is written to a new file that is only readable by its owner from the start, then moved over the old one,
+    //which may have been created with a wider mode before the secret was stored
+    let tmp = path.with_extension("tmp");
+    let _ = fs::remove_file(&tmp);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options.open(&tmp)
+      .and_then(|mut file| file.write_all(serde_json::to_string_pretty(self).unwrap().as_bytes()))
+      .map_err(|error| RecError::Io(tmp.clone(), error))?;
+    fs::rename(&tmp, &path).map_err(|error| RecError::Io(path, error))
+  }
+
+  //the config as shown by "rec config", without the api secret
+  pub fn to_display_string(&self) -> String {
+    let mut res = self.clone();
+    if res.api_secret.is_some() {
+      res.api_secret = Some(String::from("********"));
+    }
+    serde_json::to_string_pretty(&res).unwrap()
+  }
+
+  pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+    *self = set_key(self, key, value)?;
+    Ok(())
+  }
+
+  pub fn has_profile(&self, name: &str) -> bool {
+    name == DEFAULT_PROFILE || self.profiles.contains_key(name)
+  }
+
+  //seconds a problem of the rating is expected to take, 800 -> 30 minutes and 2000 -> 150 minutes by default
+  pub fn time_budget(&self, rating: i64) -> i64 {
+    let minutes = self.time_budget_base + cmp::max(rating - 800, 0) / 100 * self.time_budget_step;
+    cmp::max(minutes, 1) * 60
+  }
+
+  //in seconds, datasets left out of cache_ttl_hours keep their default
+  pub fn cache_ttl(&self, dataset: &str) -> i64 {
+    let hours = self.cache_ttl_hours.get(dataset).copied()
+                    .or_else(|| default_cache_ttl_hours().get(dataset).copied())
+                    .unwrap_or(24);
+    hours * 60 * 60
+  }
+
+  //the settings of the profile in use, a profile without an entry uses the defaults
+  pub fn active_profile(&self) -> ProfileConfig {
+    self.profile_config(&self.profile)
+  }
+
+  pub fn profile_config(&self, name: &str) -> ProfileConfig {
+    self.profiles.get(name).cloned().unwrap_or_default()
+  }
+}
+
+impl ProfileConfig {
+  pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+    *self = set_key(self, key, value)?;
+    Ok(())
+  }
+}
+
+//set a single key from the commandline, the value is taken as json if it parses, otherwise as a plain string
+fn set_key<T: Serialize + DeserializeOwned>(target: &T, key: &str, value: &str) -> Result<T, String> {
+  let parsed: Value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+  let res = match with_value(target, key, parsed.clone()) {
+    Ok(res) => res,
+    //an object or array was meant as json, its own error says more than the plain string's
+    Err(error) if parsed.is_object() || parsed.is_array() => return Err(format!("invalid value for \"{}\": {}", key, error)),
+    Err(_) => with_value(target, key, Value::String(value.to_string()))
+                .map_err(|error| format!("invalid value for \"{}\": {}", key, error))?,
+  };
+  if serde_json::to_value(&res).unwrap().get(key).is_none() {
+    return Err(format!("unknown config key \"{}\"", key));
+  }
+  Ok(res)
+}
+
+fn with_value<T: Serialize + DeserializeOwned>(target: &T, key: &str, value: Value) -> Result<T, serde_json::Error> {
+  let mut res = serde_json::to_value(target).unwrap();
+  res.as_object_mut().unwrap().insert(key.to_string(), value);
+  serde_json::from_value(res)
+}
+
+//the handle is taken from the --handle flag first, then the profile's own handle, then the REC_HANDLE environment
+//variable, then the config file, so that switching profiles switches handles even with REC_HANDLE exported
+pub fn resolve_handle(flag: Option<String>, config: &Config, profile: &str) -> Option<String> {
+  flag.or_else(|| config.profiles.get(profile).and_then(|profile| profile.handle.clone()))
+      .or_else(|| env::var("REC_HANDLE").ok())
+      .or_else(|| config.handle.clone())
+      .filter(|handle| !handle.is_empty())
+}
@@ -0,0 +1,88 @@
+use std::{fmt, io};
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum RecError {
+  //the request never got a response: no connection, dns failure, timeout
+  Network(String, reqwest::Error),
+  //the server answered with a non-2xx status code
+  HttpStatus(String, u16),
+  //codeforces answered with status FAILED and a comment
+  ApiFailed(String, String),
+  //a response or a cache file doesn't have the expected shape
+  Json(String, String),
+  Io(PathBuf, io::Error),
+  //the config is missing something the command needs
+  Config(String),
+  //the commandline arguments can't be understood
+  Usage(String),
+}
+
+impl RecError {
+  pub fn exit_code(&self) -> i32 {
+    match self {
+      RecError::Network(..) => 2,
+      RecError::HttpStatus(..) => 3,
+      RecError::ApiFailed(..) => 4,
+      RecError::Json(..) => 5,
+      RecError::Io(..) => 6,
+      RecError::Config(..) => 7,
+      RecError::Usage(..) => 1,
+    }
+  }
+
+  //worth asking the same server again after a while: timeouts, server errors and the API call limit
+  pub fn is_transient(&self) -> bool {
+    match self {
+      RecError::Network(_, error) => error.is_timeout(),
+      RecError::HttpStatus(_, status) => *status >= 500,
+      RecError::ApiFailed(_, comment) => comment.contains("Call limit exceeded"),
+      _ => false,
+    }
+  }
+
+  pub fn json(source: &str, detail: impl fmt::Display) -> RecError {
+    RecError::Json(source.to_string(), detail.to_string())
+  }
+}
+
+impl fmt::Display for RecError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      RecError::Network(url, error) => write!(f, "can't reach {}: {}", url, error),
+      RecError::HttpStatus(url, status) => write!(f, "{} answered with HTTP status {}", url, status),
+      RecError::ApiFailed(url, comment) => write!(f, "codeforces API call {} failed: {}", url, comment),
+      RecError::Json(source, detail) => write!(f, "unexpected data in {}: {}", source, detail),
+      RecError::Io(path, error) => write!(f, "can't access {}: {}", path.display(), error),
+      RecError::Config(detail) => write!(f, "config: {}", detail),
+      RecError::Usage(detail) => write!(f, "{}", detail),
+    }
+  }
+}
+
+impl std::error::Error for RecError {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn transient_errors() {
+    let url = String::from("https://codeforces.com/api/user.info");
+    assert!(RecError::HttpStatus(url.clone(), 502).is_transient());
+    assert!(RecError::HttpStatus(url.clone(), 503).is_transient());
+    assert!(RecError::ApiFailed(url.clone(), String::from("Call limit exceeded")).is_transient());
+  }
+
+  #[test]
+  fn lasting_errors() {
+    let url = String::from("https://codeforces.com/api/user.info");
+    assert!(!RecError::HttpStatus(url.clone(), 404).is_transient());
+    assert!(!RecError::ApiFailed(url.clone(), String::from("handles: User with handle nobody not found")).is_transient());
+    assert!(!RecError::json(&url, "missing field `result`").is_transient());
+    assert!(!RecError::Io(PathBuf::from("config"), io::Error::from(io::ErrorKind::NotFound)).is_transient());
+    //nothing listens on port 1, the connection is refused rather than timed out
+    let refused = reqwest::blocking::get("http://127.0.0.1:1/").unwrap_err();
+    assert!(!RecError::Network(url, refused).is_transient());
+  }
+}
@@ -0,0 +1,177 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use chrono::{Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+use crate::error::RecError;
+use crate::storage::Storage;
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Display, EnumString)]
+pub enum Outcome {
+  #[strum(serialize = "bind")]
+  Bind,
+  #[strum(serialize = "solved")]
+  Solved,
+  #[strum(serialize = "unsolved")]
+  Unsolved,
+  #[strum(serialize = "dropped")]
+  Dropped,
+}
+
+//one line of the history file, written once and never changed afterwards
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+  pub time: i64,
+  pub problem_id: String,
+  pub rating: i64,
+  pub outcome: Outcome,
+  pub diff_before: i64,
+  pub diff_after: i64,
+  pub streak_before: i64,
+  pub streak_after: i64,
+  //seconds from bind to the outcome without the pauses, none for binds and problems bound before the clock existed
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub duration: Option<i64>,
+  //for solved problems, whether codeforces had an accepted submission, none before solves were checked
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub verified: Option<bool>,
+}
+
+pub fn append(storage: &Storage, entry: &HistoryEntry) -> Result<(), RecError> {
+  let path = storage.profile_file("history");
+  let line = serde_json::to_string(entry).map_err(|error| RecError::json("history", error))? + "\n";
+  OpenOptions::new().create(true).append(true).open(&path)
+    .and_then(|mut file| file.write_all(line.as_bytes()))
+    .map_err(|error| RecError::Io(path, error))
+}
+
+pub fn read(storage: &Storage) -> Result<Vec<HistoryEntry>, RecError> {
+  let path = storage.profile_file("history");
+  if !path.exists() {
+    return Ok(Vec::new());
+  }
+  let content = fs::read_to_string(&path).map_err(|error| RecError::Io(path.clone(), error))?;
+  let mut res: Vec<HistoryEntry> = Vec::new();
+  for (i, line) in content.lines().enumerate() {
+    if line.trim().is_empty() {
+      continue;
+    }
+    let entry = serde_json::from_str(line).map_err(|error| RecError::json(&format!("{} line {}", path.display(), i + 1), error))?;
+    res.push(entry);
+  }
+
+  Ok(res)
+}
+
+pub struct HistoryFilter {
+  pub from: Option<i64>,
+  pub to: Option<i64>,
+  pub outcome: Vec<Outcome>,
+}
+
+pub fn filter_history(history: &[HistoryEntry], filter: &HistoryFilter) -> Vec<HistoryEntry> {
+  history.iter()
+         .filter(|entry| filter.from.is_none_or(|from| entry.time >= from))
+         .filter(|entry| filter.to.is_none_or(|to| entry.time < to))
+         .filter(|entry| filter.outcome.is_empty() || filter.outcome.contains(&entry.outcome))
+         .cloned()
+         .collect()
+}
+
+//local midnight at the start of the date, "2022-12-31" -> timestamp
+pub fn parse_date(date: &str) -> Option<i64> {
+  let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+  Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest().map(|time| time.timestamp())
+}
+
+pub fn format_time(time: i64) -> String {
+  format_local(time, "%Y-%m-%d %H:%M")
+}
+
+pub fn format_date(time: i64) -> String {
+  format_local(time, "%Y-%m-%d")
+}
+
+fn format_local(time: i64, format: &str) -> String {
+  match Local.timestamp_opt(time, 0).single() {
+    Some(time) => time.format(format).to_string(),
+    None => time.to_string(),
+  }
+}
+
+//"1h30m" -> 5400, "45m" -> 2700, "2h" -> 7200, a bare number counts as minutes
+pub fn parse_duration(arg: &str) -> Option<i64> {
+  if let Ok(minutes) = arg.parse::<i64>() {
+    return minutes.checked_mul(60).filter(|seconds| *seconds > 0);
+  }
+  let mut res: i64 = 0;
+  let mut number = String::new();
+  for c in arg.chars() {
+    if c.is_ascii_digit() {
+      number.push(c);
+      continue;
+    }
+    let unit = match c {
+      'd' => 86400,
+      'h' => 3600,
+      'm' => 60,
+      _ => return None,
+    };
+    res = res.checked_add(number.parse::<i64>().ok()?.checked_mul(unit)?)?;
+    number.clear();
+  }
+  (number.is_empty() && res > 0).then_some(res)
+}
+
+//3725 -> "1h 2m", 90000 -> "1d 1h 0m"
+pub fn format_duration(seconds: i64) -> String {
+  let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+  if days > 0 {
+    format!("{}d {}h {}m", days, hours, minutes)
+  } else if hours > 0 {
+    format!("{}h {}m", hours, minutes)
+  } else {
+    format!("{}m", minutes)
+  }
+}
+
+pub fn now() -> i64 {
+  Local::now().timestamp()
+}
+
+pub fn print_history(history: &[HistoryEntry]) {
+  println!();
+  println!("|       time       | outcome  |   id   | rating |  recommended_diff  |  streak  | time taken ");
+  for entry in history {
+    let diff = format!("{} -> {}", entry.diff_before, entry.diff_after);
+    let streak = format!("{} -> {}", entry.streak_before, entry.streak_after);
+    let duration = entry.duration.map(format_duration).unwrap_or_else(|| String::from("-"));
+    let outcome = if entry.verified == Some(false) { format!("{}*", entry.outcome) } else { entry.outcome.to_string() };
+    println!("|{:^18}|{:^10}|{:^8}|{:^8}|{:^20}|{:^10}|{:^12}", format_time(entry.time), outcome, entry.problem_id, entry.rating, diff, streak, duration);
+  }
+  if history.iter().any(|entry| entry.verified == Some(false)) {
+    println!("* solved with --force, without an accepted submission");
+  }
+  println!();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_duration_forms() {
+    assert_eq!(parse_duration("90"), Some(90 * 60));
+    assert_eq!(parse_duration("45m"), Some(45 * 60));
+    assert_eq!(parse_duration("2h"), Some(2 * 3600));
+    assert_eq!(parse_duration("1h30m"), Some(5400));
+    assert_eq!(parse_duration("1d2h"), Some(86400 + 2 * 3600));
+  }
+
+  #[test]
+  fn parse_duration_rejects() {
+    for arg in ["", "0", "-5", "0m", "h", "1h30", "1.5h", "1 h", "30s", "999999999999999999", "999999999999999d", "153722867280912930m1m"] {
+      assert_eq!(parse_duration(arg), None, "{}", arg);
+    }
+  }
+}
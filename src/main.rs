@@ -1,83 +1,264 @@
+#[allow(non_snake_case)]
 mod DTOs;
 mod problem;
+mod config;
+mod storage;
+mod error;
+mod api;
+mod profile;
+mod history;
+mod stats;
+mod rating;
+mod cache;
+mod selection;
+mod watch;
 
-use std::path::Path;
-use std::{cmp, env, fs};
-use std::collections::{HashMap, HashSet};
+use std::{env, process};
+use std::path::PathBuf;
 use std::str::FromStr;
-use rand::distributions::{Distribution, Uniform};
-use serde_json::Value;
-use serde::{Deserialize, Serialize};
 use crate::DTOs::DTOs::*;
 use strum_macros::EnumString;
 use crate::problem::*;
+use crate::config::*;
+use crate::storage::Storage;
+use crate::error::RecError;
+use crate::api::Api;
+use crate::profile::profile_command;
+use crate::history::*;
+use crate::stats::print_stats;
+use crate::cache::*;
+use crate::watch::watch_command;
 use terminal_link::Link;
 
 fn print_description() {
   println!("rec - commandline codeforces problem recommender [version 1.0.0]");
   println!();
-  println!("rec is a tool for practicing codeforces problems, with a classic Elo rating system to");
+  println!("rec is a tool for practicing codeforces problems, with an Elo or Glicko-2 rating system to");
   println!("evaluate user's problem solving skill, and try to recommend problems that are challenging");
   println!("for the user in order to provide an effective way of training.");
   println!();
-  println!("Usage:  rec subcommand");
+  println!("Usage:  rec [--handle handle] [--data-dir directory] [--profile name] subcommand");
+  println!();
+  println!("The codeforces handle is taken from --handle, then the profile in use, then the REC_HANDLE");
+  println!("environment variable, then the config file (set it once with \"rec config handle your_handle\").");
+  println!("Downloaded data is cached in the user cache directory and the practice progress is kept in");
+  println!("the user data directory, --data-dir (or the data_dir config key) keeps both in one directory instead.");
+  println!("The api_base_url and site_base_url config keys point rec at a codeforces mirror, and api_mirrors");
+  println!("lists more API base urls to try in order when one can't be reached.");
+  println!("Timeouts, server errors and \"Call limit exceeded\" answers are retried api_max_attempts times");
+  println!("with a growing wait in between before moving on to the next mirror.");
+  println!("With api_key and api_secret set (from https://codeforces.com/settings/api) submissions and user");
+  println!("info are fetched with signed requests, which also see handles with restricted visibility. Only");
+  println!("api_base_url gets signed requests, the mirrors are sent the same requests without the key.");
   println!();
   println!("Some useful subcommands:");
-  println!("  bind                           bind a new problem.");
-  println!("  solved                         tell the program you solved the binded problem, and to unbind it.");
+  println!("  bind [--debug] [--weakness]    bind a new problem, --debug shows the likeliest candidates first,");
+  println!("                                 --weakness picks problems with the tags you are weakest at.");
+  println!("  solved [--force]               tell the program you solved the binded problem, and to unbind it. It");
+  println!("                                 needs an accepted submission on codeforces, --force takes the solve");
+  println!("                                 anyway and marks it as unverified.");
   println!("  unsolved                       tell the program you didn't solve the binded problem, and to unbind it.");
   println!("  drop                           unbind the problem, this will not change your Elo rating of practice.");
-  println!("  update                         pull data from codeforces API, this may take a while.");
-  println!("  query difficulty [flags...]    query problems satisfy the requirement and certain integer difficulty.");
+  println!("  status                         show the practice state and how long the binded problem has taken.");
+  println!("  pause                          stop the clock of the binded problem for a break.");
+  println!("  resume                         start the clock again.");
+  println!("  update [--full] [--if-stale]   pull data from codeforces API, only new submissions are fetched");
+  println!("                                 unless --full is given, and with --if-stale only the data older");
+  println!("                                 than its cache_ttl_hours config entry is pulled.");
+  println!("  cache status                   show the age, size and record count of the cached data.");
+  println!("  query difficulty [flags...]    query problems satisfy the requirement and the difficulty, which is a");
+  println!("                                 rating like 1900, a range like 1900-2300, or \">=2400\" and \"<=1600\".");
   println!("  upsolveList                    show the problems on the upsolve list.");
   println!("  upsolved problem_id            remove the problem from the upsolve list.");
+  println!("  config [key value]             show the config file, or set a key in it.");
+  println!("  profile [list]                 list the profiles, the one in use is marked with *.");
+  println!("  profile create name [handle]   create a profile with its own handle and practice progress.");
+  println!("  profile switch name            use another profile from now on, --profile name uses it once.");
+  println!("  profile delete name            delete a profile and its practice progress.");
+  println!("  profile set key value          set a key for the profile in use, e.g. rating_model glicko2.");
+  println!("  history [flags...]             list every bind, solved, unsolved and drop of the profile.");
+  println!("  stats                          show how the practice is going, from the history and your submissions,");
+  println!("                                 with a rating for every tag practiced.");
+  println!("  watch [flags...]               check your submissions every 30 seconds, report the verdicts on the");
+  println!("                                 binded problem and mark it solved once one is accepted.");
   println!();
   println!("Some flags for query command:");
   println!("  -d1       query div. 1 problems.");
   println!("  -d2       query div. 2 problems.");
   println!("  -d12      query div. 1 + 2 problems.");
+  println!("  -d3       query div. 3 problems.");
+  println!("  -d4       query div. 4 problems.");
+  println!("  -kotlin   query kotlin heroes problems.");
+  println!("  -fools    query april fools problems.");
+  println!("  -team     query team contest problems.");
+  println!("  -unofficial  query problems of unofficial and unrated contests.");
   println!("  -edu      query educational problems.");
   println!("  -gl       query global round problems.");
+  println!("  -gym      query problems of the gym contests listed in the gym_contests config key.");
   println!("  -other    query problems that are not fall into the above categories.");
-  println!("  -old      allow query old problems.");
-  println!("  -rec      query the most recent 10 problems satisfy the requirement.");
+  println!("  -old      allow query old problems, by default only contests of the last recent_years years count.");
+  println!("  -since date      query problems of contests from the date on, e.g. 2021-01-01.");
+  println!("  -years n         query problems of contests from the last n years.");
+  println!("  -rec      query the most recent 10 problems satisfy the requirement, the same as -old -n 10.");
+  println!("  -n count         query at most count problems, the most recent first.");
+  println!("  -idx C-E         query problems with an index from C to E, -idx C only takes C problems.");
+  println!("  --sort key       sort by rating, date, contest, index or solvedcount, add --desc for descending order.");
+  println!("  --random n       query a random sample of n problems, --seed s draws the same sample again.");
+  println!("  -tag t1,t2       query problems with at least one of the tags.");
+  println!("  -alltag t1,t2    query problems with all of the tags.");
+  println!("  -notag t1,t2     query problems with none of the tags.");
+  println!("  -minsolved n     query problems solved by at least n people.");
+  println!("  -maxsolved n     query problems solved by at most n people.");
+  println!();
+  println!("Some flags for watch command:");
+  println!("  --interval seconds       check every given seconds instead of 30, at least 10.");
+  println!("  --deadline time          mark the problem unsolved once its solving time reaches 90m, 2h or 1h30m.");
+  println!();
+  println!("Some flags for history command:");
+  println!("  -from date               only list events on or after the date, e.g. 2022-12-01.");
+  println!("  -to date                 only list events on or before the date.");
+  println!("  -outcome o1,o2           only list these outcomes: bind, solved, unsolved, dropped.");
+  println!();
+  println!("bind only picks problems with a tag from the tag_whitelist config key (when it isn't empty),");
+  println!("and never picks problems with a tag from tag_blacklist.");
+  println!("With bind_popularity set to \"avoid_obscure\" it skips problems solved by fewer than");
+  println!("obscure_solved_count people (500 by default), \"prefer_obscure\" picks only those, either way");
+  println!("it falls back to any problem when none is left.");
+  println!();
+  println!("bind picks problems close to the target rating, from recent contests and with many solvers more");
+  println!("often. The bind_weight_distance, bind_weight_recency, bind_weight_popularity and");
+  println!("bind_weight_division config keys set how much each of these counts, 0 ignores it and a negative");
+  println!("bind_weight_popularity favours obscure problems. bind_division_preference rates divisions, e.g.");
+  println!("  {{\"Div1\": 1.0, \"Div12\": 0.8, \"GlobalRound\": 0.5}}");
+  println!();
+  println!("Every solved or unsolved problem also moves a rating for each of its tags, bind --weakness picks");
+  println!("problems with one of the three tags rated furthest below recommended_diff, counting only tags");
+  println!("with at least two results.");
+  println!();
+  println!("Gym contests are added by their id to the gym_contests config key, e.g. [102012, 102013], and");
+  println!("their problems are fetched by the next update. Gym problems have no rating, so it is estimated");
+  println!("from the contest's difficulty stars and how many teams solved the problem.");
+  println!();
+  println!("Contests are sorted into divisions by the first rule whose pattern is in the contest name, a file");
+  println!("named divisions next to the config file replaces the built-in rules, it looks like");
+  println!("  [{{\"pattern\": \"Div. 3\", \"division\": \"Div3\"}}, {{\"pattern\": \"Div. 1\", \"contest_type\": \"CF\", \"division\": \"Div1\"}}]");
+  println!();
+  println!("Each profile picks its rating model with its rating_model key: \"elo\" (the default) moves the");
+  println!("recommended difficulty by a fixed step, \"glicko2\" also tracks how certain the rating is and");
+  println!("moves it faster while it is uncertain.");
+  println!();
+  println!("With time_aware_rating set to true a slow solve counts as less than a full solve: a problem is");
+  println!("expected to take time_budget_base minutes at rating 800 plus time_budget_step minutes for every");
+  println!("100 rating above it, and a solve taking twice the budget counts half as much.");
 }
 
-fn print_guide() {
-  println!("Invalid arguments!");
-  println!("You may enter \"rec help\" for help.");
+fn invalid_arguments() -> RecError {
+  RecError::Usage(String::from("Invalid arguments! You may enter \"rec help\" for help."))
 }
 
-fn update_all_DTOs(user_handle: &String) {
-  update_problemDTOs();
-  update_contestDTOs();
-  update_submissionDTOs(&user_handle);
-  UserInfoDTO::update(&user_handle);
+fn print_missing_handle() {
+  eprintln!("No codeforces handle is set!");
+  eprintln!("Pass --handle your_handle, set the REC_HANDLE environment variable,");
+  eprintln!("or save it with \"rec config handle your_handle\".");
 }
 
-fn query_problems(args: &Vec<String>, problems: &Vec<Problem>, user_handle: &String) -> Vec<Problem> {
+//with if_stale only the datasets older than their ttl are fetched
+#[allow(non_snake_case)]
+fn update_all_DTOs(user_handle: &str, full: bool, if_stale: bool, storage: &Storage, api: &Api, config: &Config) -> Result<(), RecError> {
+  let update = |dataset: &str, update: &dyn Fn() -> Result<(), RecError>| {
+    if !if_stale || is_stale(storage, config, dataset, user_handle) {
+      update()
+    } else {
+      Ok(())
+    }
+  };
+  update("problems", &|| update_problemDTOs(storage, api))
+    .and_then(|_| update("contests", &|| update_contestDTOs(storage, api)))
+    //only the gym standings that aren't cached yet are fetched, unless full is set
+    .and_then(|_| update_gymDTOs(full, storage, api, config))
+    .and_then(|_| update("submissions", &|| update_submissionDTOs(user_handle, full, storage, api)))
+    .and_then(|_| update("user_info", &|| UserInfoDTO::update(user_handle, storage, api)))
+    .inspect_err(|_| println!("The cached data that couldn't be fetched was left untouched."))
+}
+
+fn query_problems(args: &[String], problems: &Vec<Problem>, user_handle: &str, storage: &Storage, api: &Api, config: &Config) -> Result<Vec<Problem>, RecError> {
   let mut div: Vec<Division> = Vec::new();
   let mut pool_size: Option<i64> = None;
-  let mut round = 1480;
-  let diff = args[2].parse::<i64>().unwrap();
-  for element in args {
-    match Flag::from_str(&element) {
-      Ok(flag) => match flag {
-        Flag::Div1 => div.push(Division::Div1),
-        Flag::Div2 => div.push(Division::Div2),
-        Flag::Div12 => div.push(Division::Div12),
-        Flag::GlobalRound => div.push(Division::GlobalRound),
-        Flag::Educational => div.push(Division::Educational),
-        Flag::Other => div.push(Division::Other),
-        Flag::ContainOldProblems => round = 1364,
-        Flag::RecentMode => pool_size = Some(10),
+  let mut recency = Some(Recency::Years(config.recent_years));
+  let mut recent_mode = false;
+  let mut index_range: Option<(String, String)> = None;
+  let mut tags_any: Vec<String> = Vec::new();
+  let mut tags_all: Vec<String> = Vec::new();
+  let mut tags_none: Vec<String> = Vec::new();
+  let mut sort: Option<SortKey> = None;
+  let mut descending = false;
+  let mut random: Option<i64> = None;
+  let mut seed: Option<u64> = None;
+  let mut min_solved: Option<i64> = None;
+  let mut max_solved: Option<i64> = None;
+  let (min_diff, max_diff) = parse_rating_range(&args[2])?;
+  let mut i = 3;
+  while i < args.len() {
+    let Ok(flag) = Flag::from_str(&args[i]) else {
+      return Err(RecError::Usage(format!("Unknown query flag \"{}\", see \"rec help\".", args[i])));
+    };
+    //the flags that take a value consume the next argument
+    let value = || args.get(i + 1).map(|value| value.as_str()).ok_or_else(|| RecError::Usage(format!("{} needs a value.", args[i])));
+    match flag {
+      Flag::Div1 => div.push(Division::Div1),
+      Flag::Div2 => div.push(Division::Div2),
+      Flag::Div12 => div.push(Division::Div12),
+      Flag::Div3 => div.push(Division::Div3),
+      Flag::Div4 => div.push(Division::Div4),
+      Flag::KotlinHeroes => div.push(Division::KotlinHeroes),
+      Flag::AprilFools => div.push(Division::AprilFools),
+      Flag::Team => div.push(Division::Team),
+      Flag::Unofficial => div.push(Division::Unofficial),
+      Flag::GlobalRound => div.push(Division::GlobalRound),
+      Flag::Educational => div.push(Division::Educational),
+      Flag::Other => div.push(Division::Other),
+      Flag::Gym => div.push(Division::Gym),
+      Flag::ContainOldProblems => recency = None,
+      Flag::RecentMode => recent_mode = true,
+      Flag::Since => {
+        let value = value()?;
+        let time = parse_date(value).ok_or_else(|| RecError::Usage(format!("Invalid date \"{}\" for -since, dates look like 2021-01-01.", value)))?;
+        recency = Some(Recency::Since(time));
+      },
+      Flag::Years => {
+        let value = value()?;
+        let years = parse_positive(value, "-years")?;
+        recency = Some(Recency::years(years).ok_or_else(|| RecError::Usage(format!("Invalid number \"{}\" for -years, that's too many years.", value)))?);
+      },
+      Flag::Limit => pool_size = Some(parse_positive(value()?, "-n")?),
+      Flag::Index => index_range = Some(parse_index_range(value()?)?),
+      Flag::TagAny => tags_any.extend(split_tags(value()?)),
+      Flag::TagAll => tags_all.extend(split_tags(value()?)),
+      Flag::NoTag => tags_none.extend(split_tags(value()?)),
+      Flag::MinSolved => min_solved = Some(parse_positive(value()?, "-minsolved")?),
+      Flag::MaxSolved => max_solved = Some(parse_positive(value()?, "-maxsolved")?),
+      Flag::Sort => {
+        let value = value()?;
+        sort = Some(SortKey::from_str(value).map_err(|_| RecError::Usage(format!("Can't sort by \"{}\", use rating, date, contest, index or solvedcount.", value)))?);
+      },
+      Flag::Ascending => descending = false,
+      Flag::Descending => descending = true,
+      Flag::Random => random = Some(parse_positive(value()?, "--random")?),
+      Flag::Seed => {
+        let value = value()?;
+        seed = Some(value.parse::<u64>().map_err(|_| RecError::Usage(format!("Invalid seed \"{}\", it has to be a non-negative integer.", value)))?);
       },
-      Err(error) => ()
     }
+    i += match flag {
+      Flag::Since | Flag::Years | Flag::Limit | Flag::Index | Flag::TagAny | Flag::TagAll | Flag::NoTag | Flag::MinSolved | Flag::MaxSolved | Flag::Sort | Flag::Random | Flag::Seed => 2,
+      _ => 1,
+    };
   }
 
-  if let Some(size) = pool_size {
-    round = 0;
+  //-rec is -old -n 10, -n still sets another count
+  if recent_mode {
+    recency = None;
+    pool_size = pool_size.or(Some(10));
   }
 
   //if no specified division requirement, set the default division be rounds rated for Div.1 user
@@ -85,42 +266,104 @@ fn query_problems(args: &Vec<String>, problems: &Vec<Problem>, user_handle: &Str
     div = vec![Division::Div1, Division::Div12, Division::GlobalRound];
   }
   
+  //with --sort or --random, -n counts after the whole filtered set is sampled and sorted
+  let reorder = sort.is_some() || random.is_some();
   let filter_options = FilterOptions {
-    min_diff: diff,
-    max_diff: diff,
-    oldest_round: Some(round),
-    div: div,
-    user: Some(User::new(&user_handle)),
-    pool_size: pool_size,
+    min_diff,
+    max_diff,
+    recency,
+    div,
+    user: Some(User::new(user_handle, storage, api)?),
+    pool_size: if reorder { None } else { pool_size },
+    index_range,
+    tags_any,
+    tags_all,
+    tags_none,
+    min_solved,
+    max_solved,
+  };
+
+  let mut res = filter_problems(problems, &filter_options);
+  if let Some(count) = random {
+    res = sample_problems(&res, count as usize, seed);
+  }
+  if let Some(key) = sort {
+    sort_problems(&mut res, key, descending);
+  }
+  if let (true, Some(pool_size)) = (reorder, pool_size) {
+    res.truncate(pool_size as usize);
+  }
+
+  Ok(res)
+}
+
+//"1900" -> 1900..=1900, "1900-2300" -> 1900..=2300, ">=2400" -> 2400.., "<=1600" -> ..=1600
+fn parse_rating_range(arg: &str) -> Result<(i64, i64), RecError> {
+  let invalid = || RecError::Usage(format!("Invalid difficulty \"{}\", use 1900, 1900-2300, >=2400 or <=1600.", arg));
+  let number = |value: &str| value.trim().parse::<i64>().map_err(|_| invalid());
+  let (min, max) = if let Some(value) = arg.strip_prefix(">=") {
+    (number(value)?, i64::MAX)
+  } else if let Some(value) = arg.strip_prefix("<=") {
+    (0, number(value)?)
+  } else if let Some((min, max)) = arg.split_once('-') {
+    (number(min)?, number(max)?)
+  } else {
+    (number(arg)?, number(arg)?)
   };
-  let mut count: i64 = 0;
+  if min > max {
+    return Err(invalid());
+  }
+
+  Ok((min, max))
+}
+
+//"C-E" -> ("C", "E"), "B" -> ("B", "B")
+fn parse_index_range(arg: &str) -> Result<(String, String), RecError> {
+  let (first, last) = arg.split_once('-').unwrap_or((arg, arg));
+  let (first, last) = (first.trim().to_uppercase(), last.trim().to_uppercase());
+  let valid = |index: &str| !index.is_empty() && index.chars().all(|c| c.is_ascii_alphanumeric());
+  if !valid(&first) || !valid(&last) || first > last {
+    return Err(RecError::Usage(format!("Invalid index range \"{}\" for -idx, use C or C-E.", arg)));
+  }
 
-  filter_problems(&problems, &filter_options)
+  Ok((first, last))
 }
 
-fn print_problems(problems: &Vec<Problem>) {
+fn parse_positive(arg: &str, flag: &str) -> Result<i64, RecError> {
+  arg.parse::<i64>().ok().filter(|value| *value > 0)
+     .ok_or_else(|| RecError::Usage(format!("Invalid number \"{}\" for {}, it has to be a positive integer.", arg, flag)))
+}
+
+//"dp,Graphs" -> ["dp", "graphs"]
+fn split_tags(arg: &str) -> Vec<String> {
+  arg.split(',').map(normalize_tag).filter(|tag| !tag.is_empty()).collect()
+}
+
+fn print_problems(problems: &Vec<Problem>, site_base_url: &str) {
   println!();
-  println!("|            problem name            | rating |   id   | contest name");
+  println!("|            problem name            | rating |   id   | solved | contest name | tags");
   for element in problems {
-    let contest_id = "#".to_owned() + &element.contest_id.to_string();
-    let url = element.problem_url();
+    let url = element.problem_url(site_base_url);
     let problem_name = format!("{:^36}", element.name);
     let hyper_link = Link::new(&problem_name[..], &url);
-    println!("|{:^36}|{:^8}|{:^8}| {}", hyper_link, element.rating, element.combined_id(), element.contest_name);
+    //gym ratings are estimates
+    let rating = if element.div == Division::Gym { format!("~{}", element.rating) } else { element.rating.to_string() };
+    let solved = element.solved_count.map_or(String::from("-"), |count| count.to_string());
+    println!("|{:^36}|{:^8}|{:^8}|{:^8}| {} | {}", hyper_link, rating, element.combined_id(), solved, element.contest_name, element.tags.join(", "));
   }
   println!();
 }
 
-fn upsolve_problems(problems: &Vec<Problem>, user_handle: &String) -> Vec<Problem> {
+fn upsolve_problems(problems: &Vec<Problem>, user_handle: &str, storage: &Storage, api: &Api) -> Result<Vec<Problem>, RecError> {
   let mut res: Vec<Problem> = Vec::new();
-  let upsolve_set = User::new(user_handle).upsolve_problems;
+  let upsolve_set = User::new(user_handle, storage, api)?.upsolve_problems;
   for element in problems {
     if upsolve_set.contains(&element.combined_id()) {
-      res.push(element.clone()); 
+      res.push(element.clone());
     }
   }
 
-  res
+  Ok(res)
 }
 
 #[derive(EnumString)]
@@ -143,6 +386,24 @@ enum Command {
   UpsolveList,
   #[strum(serialize = "upsolved")]
   Upsolved,
+  #[strum(serialize = "config")]
+  Config,
+  #[strum(serialize = "profile")]
+  Profile,
+  #[strum(serialize = "history")]
+  History,
+  #[strum(serialize = "stats")]
+  Stats,
+  #[strum(serialize = "status")]
+  Status,
+  #[strum(serialize = "pause")]
+  Pause,
+  #[strum(serialize = "resume")]
+  Resume,
+  #[strum(serialize = "cache")]
+  Cache,
+  #[strum(serialize = "watch")]
+  Watch,
 }
 
 #[derive(EnumString)]
@@ -153,43 +414,255 @@ enum Flag {
   Div2,
   #[strum(serialize = "-d12")]
   Div12,
+  #[strum(serialize = "-d3")]
+  Div3,
+  #[strum(serialize = "-d4")]
+  Div4,
+  #[strum(serialize = "-kotlin")]
+  KotlinHeroes,
+  #[strum(serialize = "-fools")]
+  AprilFools,
+  #[strum(serialize = "-team")]
+  Team,
+  #[strum(serialize = "-unofficial")]
+  Unofficial,
   #[strum(serialize = "-gl")]
   GlobalRound,
   #[strum(serialize = "-edu")]
   Educational,
   #[strum(serialize = "-other")]
   Other,
+  #[strum(serialize = "-gym")]
+  Gym,
   #[strum(serialize = "-old")]
   ContainOldProblems,
+  #[strum(serialize = "-since")]
+  Since,
+  #[strum(serialize = "-years")]
+  Years,
   #[strum(serialize = "-rec")]
-  RecentMode, //ignore the round# restriction, take the most recent 10 problems
+  RecentMode, //ignore the contest date restriction, take the most recent 10 problems
+  #[strum(serialize = "-n")]
+  Limit,
+  #[strum(serialize = "-idx")]
+  Index,
+  #[strum(serialize = "--sort")]
+  Sort,
+  #[strum(serialize = "--asc")]
+  Ascending,
+  #[strum(serialize = "--desc")]
+  Descending,
+  #[strum(serialize = "--random")]
+  Random,
+  #[strum(serialize = "--seed")]
+  Seed,
+  #[strum(serialize = "-tag")]
+  TagAny,
+  #[strum(serialize = "-alltag")]
+  TagAll,
+  #[strum(serialize = "-notag")]
+  NoTag,
+  #[strum(serialize = "-minsolved")]
+  MinSolved,
+  #[strum(serialize = "-maxsolved")]
+  MaxSolved,
 }
 
-fn main() {
-  let problemDTOs = get_problemDTOs();
-  let contestDTOs = get_contestDTOs();
-  let problems = get_problems(&problemDTOs, &contestDTOs);
-  let user_handle = String::from("__Shioko");
-  let mut recommender = ProblemRecommender::new(&user_handle);
+fn history_command(args: &[String], storage: &Storage) -> Result<(), RecError> {
+  let mut filter = HistoryFilter {
+    from: None,
+    to: None,
+    outcome: Vec::new(),
+  };
+  let mut i = 2;
+  while i < args.len() {
+    let value = args.get(i + 1).map(|value| value.as_str()).ok_or_else(|| RecError::Usage(format!("{} needs a value.", args[i])))?;
+    match args[i].as_str() {
+      "-from" | "-to" => {
+        let time = parse_date(value).ok_or_else(|| RecError::Usage(format!("Invalid date \"{}\", dates look like 2022-12-31.", value)))?;
+        //-to is inclusive, so the range ends at the start of the next day
+        if args[i] == "-from" {
+          filter.from = Some(time);
+        } else {
+          filter.to = Some(time + 24 * 60 * 60);
+        }
+      },
+      "-outcome" => {
+        for outcome in value.split(',') {
+          let outcome = Outcome::from_str(outcome)
+                          .map_err(|_| RecError::Usage(format!("Unknown outcome \"{}\", use bind, solved, unsolved or dropped.", outcome)))?;
+          filter.outcome.push(outcome);
+        }
+      },
+      _ => return Err(RecError::Usage(format!("Unknown history flag \"{}\", see \"rec help\".", args[i]))),
+    }
+    i += 2;
+  }
 
-  let args: Vec<String> = env::args().collect();
+  print_history(&filter_history(&read(storage)?, &filter));
+  Ok(())
+}
+
+//remove "name value" or "name=value" from the arguments and return the value
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+  let prefix = name.to_owned() + "=";
+  let pos = args.iter().position(|arg| arg == name || arg.starts_with(&prefix))?;
+  let arg = args.remove(pos);
+  if let Some(value) = arg.strip_prefix(&prefix) {
+    Some(value.to_string())
+  } else if pos < args.len() {
+    Some(args.remove(pos))
+  } else {
+    None
+  }
+}
+
+fn config_command(args: &[String], config: &mut Config) -> Result<(), RecError> {
+  match args.len() {
+    2 => println!("{}", config.to_display_string()),
+    4 => match config.set(&args[2], &args[3]) {
+      Ok(()) => {
+        config.save()?;
+        println!("Saved {} to {}.", args[2], Config::path().display());
+      },
+      Err(error) => return Err(RecError::Usage(format!("Can't set config: {}", error))),
+    },
+    _ => return Err(invalid_arguments()),
+  }
+  Ok(())
+}
+
+fn main() {
+  let mut args: Vec<String> = env::args().collect();
+  let handle_flag = take_option(&mut args, "--handle");
+  let data_dir_flag = take_option(&mut args, "--data-dir");
+  let profile_flag = take_option(&mut args, "--profile");
+  let mut config = unwrap_or_exit(Config::load());
   if args.len() == 1 {
     print_description();
     return;
   }
-  match Command::from_str(&args[1]) {
-    Ok(cmd) => match Command::from_str(&args[1]).unwrap() {
-      Command::Help => print_description(),
-      Command::Bind => recommender.bind_problem(&problems),
-      Command::Solved => recommender.solve_problem(),
-      Command::Unsolved => recommender.unsolve_problem(),
-      Command::Unbind => recommender.drop_problem(),
-      Command::Update => update_all_DTOs(&user_handle),
-      Command::Query if args.len() >= 3 => print_problems(&query_problems(&args, &problems, &user_handle)),
-      Command::UpsolveList => print_problems(&upsolve_problems(&problems, &user_handle)),
-      Command::Upsolved if args.len() >= 3 => User::new(&user_handle).delete_unsolved_problem(&args[2]),
-      _ => print_guide(),
-    }, 
-    Err(error) => print_guide(),
+  let cmd = unwrap_or_exit(Command::from_str(&args[1]).map_err(|_| invalid_arguments()));
+  let profile = profile_flag.unwrap_or_else(|| config.profile.clone());
+  if !config.has_profile(&profile) {
+    eprintln!("Can't find profile {}, create it with \"rec profile create {}\".", profile, profile);
+    process::exit(1);
+  }
+  let storage = Storage::new(data_dir_flag.map(PathBuf::from).or_else(|| config.data_dir.clone()), &profile);
+  let handle = resolve_handle(handle_flag, &config, &profile);
+  match cmd {
+    Command::Help => return print_description(),
+    Command::Config => return exit_on_error(config_command(&args, &mut config)),
+    _ => (),
+  }
+
+  //every command from here on reads or writes the data directory
+  storage.migrate(handle.as_deref());
+  match cmd {
+    Command::Profile => return exit_on_error(profile_command(&args, &profile, &mut config, &storage)),
+    Command::History => return exit_on_error(history_command(&args, &storage)),
+    Command::Cache => return exit_on_error(cache_command(&args, &storage, &config)),
+    _ => (),
+  }
+
+  //from here on the config describes the profile in use, it isn't saved again
+  config.profile = profile;
+  let Some(user_handle) = handle else {
+    print_missing_handle();
+    process::exit(1);
+  };
+  let api = Api::new(&config);
+  exit_on_error(run(cmd, &args, &user_handle, &storage, &api, &config));
+}
+
+fn exit_on_error(res: Result<(), RecError>) {
+  unwrap_or_exit(res)
+}
+
+fn unwrap_or_exit<T>(res: Result<T, RecError>) -> T {
+  res.unwrap_or_else(|error| {
+    eprintln!("Error: {}", error);
+    process::exit(error.exit_code());
+  })
+}
+
+#[allow(non_snake_case)]
+fn run(cmd: Command, args: &[String], user_handle: &String, storage: &Storage, api: &Api, config: &Config) -> Result<(), RecError> {
+  let flag = |name: &str| args.iter().any(|arg| arg == name);
+  //update goes first so that a broken cache can always be refetched
+  if let Command::Update = cmd {
+    return update_all_DTOs(user_handle, flag("--full"), flag("--if-stale"), storage, api, config);
+  }
+  //watch only needs the bound problem and the submissions
+  if let Command::Watch = cmd {
+    return watch_command(args, user_handle, storage, api, config);
+  }
+  let mut recommender = ProblemRecommender::new(user_handle, storage, api)?;
+  //the commands on the bound problem don't need the problem list
+  match cmd {
+    Command::Solved => return recommender.solve_problem(flag("--force"), storage, api, config),
+    Command::Unsolved => return recommender.unsolve_problem(storage, api, config),
+    Command::Unbind => return recommender.drop_problem(storage),
+    Command::Status => {
+      println!("{}", recommender);
+      return Ok(());
+    },
+    Command::Pause => return recommender.pause(storage),
+    Command::Resume => return recommender.resume(storage),
+    _ => (),
+  }
+  if let Command::Bind | Command::Query = cmd {
+    warn_stale(storage, config, user_handle);
+  }
+  let mut problemDTOs = get_problemDTOs(storage, api)?;
+  let mut contestDTOs = get_contestDTOs(storage, api)?;
+  let (gym_contestDTOs, gym_problemDTOs) = get_gymDTOs(storage, api, config);
+  problemDTOs.extend(gym_problemDTOs);
+  contestDTOs.extend(gym_contestDTOs);
+  let problems = get_problems(&problemDTOs, &contestDTOs, &DivisionClassifier::load()?);
+
+  match cmd {
+    Command::Bind => recommender.bind_problem(&problems, &BindOptions { debug: flag("--debug"), weakness: flag("--weakness") }, storage, api, config)?,
+    Command::Query if args.len() >= 3 => print_problems(&query_problems(args, &problems, user_handle, storage, api, config)?, &api.site_base_url),
+    Command::Stats => print_stats(&recommender, config.active_profile().rating_model, &read(storage)?, &problems, &get_submissionDTOs(user_handle, storage, api)?),
+    Command::UpsolveList => print_problems(&upsolve_problems(&problems, user_handle, storage, api)?, &api.site_base_url),
+    Command::Upsolved if args.len() >= 3 => User::new(user_handle, storage, api)?.delete_unsolved_problem(&args[2], storage)?,
+    _ => return Err(invalid_arguments()),
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_rating_range_forms() {
+    assert_eq!(parse_rating_range("1900").unwrap(), (1900, 1900));
+    assert_eq!(parse_rating_range("1900-2300").unwrap(), (1900, 2300));
+    assert_eq!(parse_rating_range(">=2400").unwrap(), (2400, i64::MAX));
+    assert_eq!(parse_rating_range("<=1600").unwrap(), (0, 1600));
+    assert_eq!(parse_rating_range("1900 - 2300").unwrap(), (1900, 2300));
+  }
+
+  #[test]
+  fn parse_rating_range_rejects() {
+    for arg in ["", "abc", "2300-1900", "1900-", ">=", "19OO"] {
+      assert!(matches!(parse_rating_range(arg), Err(RecError::Usage(_))), "{}", arg);
+    }
+  }
+
+  #[test]
+  fn parse_index_range_forms() {
+    assert_eq!(parse_index_range("C-E").unwrap(), (String::from("C"), String::from("E")));
+    assert_eq!(parse_index_range("b").unwrap(), (String::from("B"), String::from("B")));
+    assert_eq!(parse_index_range("A1-a2").unwrap(), (String::from("A1"), String::from("A2")));
+  }
+
+  #[test]
+  fn parse_index_range_rejects() {
+    for arg in ["", "E-C", "C-", "-E", "C+"] {
+      assert!(matches!(parse_index_range(arg), Err(RecError::Usage(_))), "{}", arg);
+    }
   }
 }
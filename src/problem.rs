@@ -1,12 +1,19 @@
-use std::path::Path;
-use std::{cmp, env, fs};
-use std::collections::{HashMap, HashSet};
+use std::{cmp, fmt};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::str::FromStr;
-use rand::distributions::{Distribution, Uniform};
-use serde_json::{Value, to_value};
+use rand::{SeedableRng, thread_rng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
 use crate::DTOs::DTOs::*;
-use strum_macros::{EnumString, Display};
+use crate::api::Api;
+use crate::config::Config;
+use crate::error::RecError;
+use crate::history::{self, HistoryEntry, Outcome};
+use crate::rating::{self, RatingState};
+use crate::selection;
+use crate::storage::{Storage, read_json, write_json};
+use strum_macros::{EnumString, EnumIter, Display};
 
 #[derive(Serialize, Deserialize)]
 pub struct Problem {
@@ -17,9 +24,16 @@ pub struct Problem {
   pub index: String,
   pub name: String,
   pub rating: i64,
+  #[serde(default)]
+  pub tags: Vec<String>,
+  //when the contest started, unknown for some gym contests
+  #[serde(default)]
+  pub start_time: Option<i64>,
+  #[serde(default)]
+  pub solved_count: Option<i64>,
 }
 
-#[derive(Serialize, Deserialize, Copy, Clone, Display)]
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Display, EnumIter)]
 pub enum Division {
   #[strum(serialize = "Div. 1")]
   Div1,
@@ -27,51 +41,133 @@ pub enum Division {
   Div2,
   #[strum(serialize = "Div. 1 + 2")]
   Div12,
+  #[strum(serialize = "Div. 3")]
+  Div3,
+  #[strum(serialize = "Div. 4")]
+  Div4,
   #[strum(serialize = "Global Round")]
   GlobalRound,
   #[strum(serialize = "Educational")]
   Educational,
+  #[strum(serialize = "Kotlin Heroes")]
+  KotlinHeroes,
+  #[strum(serialize = "April Fools")]
+  AprilFools,
+  #[strum(serialize = "Team")]
+  Team,
+  #[strum(serialize = "Unofficial")]
+  Unofficial,
+  #[strum(serialize = "Gym")]
+  Gym,
   #[strum(serialize = "Other")]
   Other,
 }
 
-#[derive(Serialize, Deserialize, Copy, Clone, EnumString)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, EnumString)]
 pub enum CompetitionSystem {
   CF,
   ICPC,
   IOI,
 }
 
-impl Division {
-  pub fn determine_division(contest_name: &String, contest_type: &CompetitionSystem) -> Division {
-    match contest_type {
-      CompetitionSystem::CF if contest_name.contains("(Div. 1)") => Division::Div1,
-      CompetitionSystem::CF if contest_name.contains("Div. 1 + Div. 2") => Division::Div12,
-      CompetitionSystem::CF if contest_name.contains("Div. 2") => Division::Div2,
-      CompetitionSystem::CF if contest_name.contains("Global Round") => Division::GlobalRound,
-      CompetitionSystem::ICPC if contest_name.contains("Educational") => Division::Educational,
-      _ => Division::Other,
-    } 
+//a contest whose name contains the pattern (ignoring case), and has the contest type if one is given, is in the division
+#[derive(Serialize, Deserialize)]
+pub struct DivisionRule {
+  pub pattern: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub contest_type: Option<CompetitionSystem>,
+  pub division: Division,
+}
+
+impl DivisionRule {
+  fn new(pattern: &str, contest_type: Option<CompetitionSystem>, division: Division) -> DivisionRule {
+    DivisionRule {
+      pattern: pattern.to_lowercase(),
+      contest_type,
+      division,
+    }
+  }
+}
+
+//the rules are tried in order and the first match wins, so the special rounds come before the plain divisions
+//and "Div. 1 + Div. 2" comes before "Div. 1" and "Div. 2"
+pub struct DivisionClassifier {
+  rules: Vec<DivisionRule>,
+}
+
+impl DivisionClassifier {
+  pub fn default_rules() -> Vec<DivisionRule> {
+    use CompetitionSystem::*;
+    vec![
+      DivisionRule::new("April Fools", None, Division::AprilFools),
+      DivisionRule::new("Kotlin Heroes", None, Division::KotlinHeroes),
+      DivisionRule::new("Teams Preferred", None, Division::Team),
+      DivisionRule::new("Preferably Teams", None, Division::Team),
+      DivisionRule::new("Teams Allowed", None, Division::Team),
+      DivisionRule::new("Unofficial Mirror", None, Division::Unofficial),
+      DivisionRule::new("unrated", None, Division::Unofficial),
+      DivisionRule::new("Educational", Some(ICPC), Division::Educational),
+      DivisionRule::new("Global Round", Some(CF), Division::GlobalRound),
+      DivisionRule::new("Div. 1 + Div. 2", Some(CF), Division::Div12),
+      DivisionRule::new("Div. 1 + 2", Some(CF), Division::Div12),
+      DivisionRule::new("Div. 1", Some(CF), Division::Div1),
+      DivisionRule::new("Div. 2", Some(CF), Division::Div2),
+      DivisionRule::new("Div. 3", None, Division::Div3),
+      DivisionRule::new("Div. 4", None, Division::Div4),
+    ]
+  }
+
+  //the rules come from the divisions file next to the config file when it exists, it replaces the whole table
+  pub fn load() -> Result<DivisionClassifier, RecError> {
+    let path = Config::path().with_file_name("divisions");
+    let rules = if path.exists() {
+      let rules: Vec<DivisionRule> = read_json(&path)?;
+      rules.into_iter().map(|rule| DivisionRule::new(&rule.pattern, rule.contest_type, rule.division)).collect()
+    } else {
+      Self::default_rules()
+    };
+    Ok(DivisionClassifier { rules })
+  }
+
+  pub fn classify(&self, contest_name: &str, contest_type: CompetitionSystem, gym: bool) -> Division {
+    if gym {
+      return Division::Gym;
+    }
+    let contest_name = contest_name.to_lowercase();
+    self.rules.iter()
+        .find(|rule| contest_name.contains(&rule.pattern) && rule.contest_type.is_none_or(|rule_type| rule_type == contest_type))
+        .map(|rule| rule.division)
+        .unwrap_or(Division::Other)
   }
 }
 
-pub fn get_problems(probDTOs: &Vec<ProblemDTO>, contDTOs: &Vec<ContestDTO>) -> Vec<Problem> {
+//problems of contests missing from the contest list, or with an unknown type, are left out
+#[allow(non_snake_case)]
+pub fn get_problems(probDTOs: &[ProblemDTO], contDTOs: &[ContestDTO], classifier: &DivisionClassifier) -> Vec<Problem> {
   let mut problems: Vec<Problem> = Vec::new();  
   let mut contestDTO_map: HashMap<i64, ContestDTO> = HashMap::new();
   for element in contDTOs {
     contestDTO_map.insert(element.id, element.clone());
   }
   for element in probDTOs {
-    let contest_name = contestDTO_map.get(&element.contest_id).unwrap().name.clone();
-    let contest_type = contestDTO_map.get(&element.contest_id).unwrap().contest_type.clone();
+    let Some(contest) = contestDTO_map.get(&element.contest_id) else {
+      continue;
+    };
+    let Ok(contest_type) = CompetitionSystem::from_str(&contest.contest_type) else {
+      continue;
+    };
     let tmp = Problem {
       contest_id: element.contest_id,
-      contest_name: contest_name.to_string(),
+      contest_name: contest.name.clone(),
       index: element.index.clone(),
       name: element.name.clone(),
       rating: element.rating,
-      div: Division::determine_division(&contest_name, &CompetitionSystem::from_str(&contest_type).unwrap()),
-      contest_type: CompetitionSystem::from_str(&contest_type).unwrap(),
+      tags: element.tags.clone(),
+      div: classifier.classify(&contest.name, contest_type, contest.gym),
+      start_time: contest.start_time,
+      solved_count: element.solved_count,
+      contest_type,
     };
     problems.push(tmp);
   }
@@ -79,12 +175,20 @@ pub fn get_problems(probDTOs: &Vec<ProblemDTO>, contDTOs: &Vec<ContestDTO>) -> V
   problems
 }
 
+impl fmt::Display for Problem {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}{} - {}", self.contest_id, self.index, self.name)
+  }
+}
+
 impl Problem {
-  pub fn problem_url(&self) -> String {
-    if self.name == String::from("") {
+  pub fn problem_url(&self, site_base_url: &str) -> String {
+    if self.name.is_empty() {
       String::from("nan")
+    } else if self.div == Division::Gym {
+      site_base_url.to_owned() + "/gym/" + &self.contest_id.to_string() + "/problem/" + &self.index
     } else {
-      String::from("https://codeforces.com/problemset/problem/") + &self.contest_id.to_string() + "/" + &self.index
+      site_base_url.to_owned() + "/problemset/problem/" + &self.contest_id.to_string() + "/" + &self.index
     }
   }
   pub fn unit() -> Self {
@@ -94,13 +198,13 @@ impl Problem {
       index: String::from(""),
       name: String::from(""),
       rating: 0,
+      tags: Vec::new(),
+      start_time: None,
+      solved_count: None,
       div: Division::Other,
       contest_type: CompetitionSystem::CF,
     }
   }
-  pub fn to_string(&self) -> String {
-    self.contest_id.to_string() + &self.index + " - " + &self.name + "\n" + &self.problem_url()
-  }
   pub fn combined_id(&self) -> String {
     self.contest_id.to_string() + &self.index
   }
@@ -111,6 +215,9 @@ impl Problem {
       index: self.index.clone(),
       name: self.name.clone(),
       rating: self.rating,
+      tags: self.tags.clone(),
+      start_time: self.start_time,
+      solved_count: self.solved_count,
       div: self.div,
       contest_type: self.contest_type,
     }
@@ -127,9 +234,10 @@ pub struct User {
 }
 
 impl User {
-  pub fn new(handle: &String) -> User {
+  #[allow(non_snake_case)]
+  pub fn new(handle: &str, storage: &Storage, api: &Api) -> Result<User, RecError> {
     let mut accepted_problems: HashSet<String> = HashSet::new();
-    let submissionDTOs = get_submissionDTOs(&handle);
+    let submissionDTOs = get_submissionDTOs(handle, storage, api)?;
     for element in &submissionDTOs {
       if element.verdict == "OK" {
         accepted_problems.insert(element.problem.contest_id.to_string() + &element.problem.index);
@@ -137,46 +245,39 @@ impl User {
     }
 
     let mut excluded_problems: HashSet<String> = HashSet::new();
-    if Path::new(&("excluded")).exists() {
-      let res: Value = serde_json::from_str(&fs::read_to_string(&("excluded")).expect("read excluded list"))
-                       .expect("convert str to json");
-      for element in res.as_array().unwrap() {
-        excluded_problems.insert(element.as_str().unwrap().to_string());  
-      }
+    let excluded_file = storage.profile_file("excluded");
+    if excluded_file.exists() {
+      excluded_problems = read_json(&excluded_file)?;
     }
 
     let mut upsolve_problems: HashSet<String> = HashSet::new();
-    if Path::new(&("upsolve")).exists() {
-      let res: Value = serde_json::from_str(&fs::read_to_string(&("upsolve")).expect("read upsolve list"))
-                       .expect("convert str to json");
-      for element in res.as_array().unwrap() {
-        upsolve_problems.insert(element.as_str().unwrap().to_string());  
-      }
+    let upsolve_file = storage.profile_file("upsolve");
+    if upsolve_file.exists() {
+      upsolve_problems = read_json(&upsolve_file)?;
     }
 
-    User {
-      handle: handle.clone(),
-      max_rating: UserInfoDTO::new(&handle).max_rating,
+    Ok(User {
+      handle: handle.to_string(),
+      max_rating: UserInfoDTO::new(handle, storage, api)?.max_rating,
       accepted_problems,
       excluded_problems,
       upsolve_problems,
-    }
+    })
   }
 
-  pub fn add_unsolved_problem(&mut self, problem: &Problem) {
+  pub fn add_unsolved_problem(&mut self, problem: &Problem, storage: &Storage) -> Result<(), RecError> {
     self.upsolve_problems.insert(problem.combined_id());
-    let res: Value = to_value(self.upsolve_problems.clone()).unwrap();
-    fs::write("upsolve", res.to_string()).ok();
+    write_json(&storage.profile_file("upsolve"), &self.upsolve_problems)
   }
 
-  pub fn delete_unsolved_problem(&mut self, problem_combined_id: &String) {
+  pub fn delete_unsolved_problem(&mut self, problem_combined_id: &String, storage: &Storage) -> Result<(), RecError> {
     if self.upsolve_problems.remove(problem_combined_id) {
-      let res: Value = to_value(self.upsolve_problems.clone()).unwrap();
-      fs::write("upsolve", res.to_string()).ok();
+      write_json(&storage.profile_file("upsolve"), &self.upsolve_problems)?;
       println!("Remove the problem from the upsolve list.");
     } else {
       println!("Can't find the problem in the upsolve list!");
     }
+    Ok(())
   }
 }
 
@@ -186,44 +287,155 @@ pub struct ProblemRecommender {
   handle: String,
   max_rating: i64,
   recommended_diff: i64,
+  //only moved by glicko-2, kept with elo so that a profile can switch models at any time
+  #[serde(default = "migrated_deviation")]
+  rating_deviation: f64,
+  #[serde(default = "initial_volatility")]
+  volatility: f64,
   bind_problem: Problem,
+  //when the problem was bound, problems bound before the clock existed have none
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  bind_time: Option<i64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  paused_at: Option<i64>,
+  //time spent in earlier pauses, not counted as solving time
+  #[serde(default)]
+  paused_seconds: i64,
   streak: i64,
+  //a rating per tag, moved by every solved or unsolved problem with the tag
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  tag_ratings: BTreeMap<String, TagRating>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TagRating {
+  pub rating: i64,
+  pub deviation: f64,
+  pub volatility: f64,
+  pub solved: i64,
+  pub unsolved: i64,
+}
+
+//how far from the target the ratings of the bind pool reach, bind_weight_distance makes the farther ones rarer
+const BIND_RATING_SPREAD: i64 = 200;
+//tags with fewer results than this aren't trusted to be weak
+const MIN_TAG_RESULTS: i64 = 2;
+//how many of the weakest tags bind --weakness aims at
+const WEAK_TAG_COUNT: usize = 3;
+
+pub struct BindOptions {
+  //print the likeliest candidates before one is picked
+  pub debug: bool,
+  //only pick problems with one of the weakest tags, as long as there are any
+  pub weakness: bool,
+}
+
+//a recommender saved before the rating models existed has been practiced on, so it starts more certain than a new one
+fn migrated_deviation() -> f64 {
+  200.0
+}
+
+fn initial_volatility() -> f64 {
+  rating::INITIAL_VOLATILITY
+}
+
+impl fmt::Display for ProblemRecommender {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "handle: {}", self.handle)?;
+    writeln!(f, "max_rating: {}", self.max_rating)?;
+    writeln!(f, "recommended_diff: {}", self.recommended_diff)?;
+    writeln!(f, "rating_deviation: {:.0}", self.rating_deviation)?;
+    writeln!(f, "volatility: {:.4}", self.volatility)?;
+    writeln!(f, "bind_problem: {}", self.bind_problem)?;
+    if let Some(elapsed) = self.elapsed() {
+      let paused = if self.paused_at.is_some() { " (paused)" } else { "" };
+      writeln!(f, "elapsed: {}{}", history::format_duration(elapsed), paused)?;
+    }
+    write!(f, "streak: {}", self.streak)
+  }
 }
 
 impl ProblemRecommender {
-  pub fn new(handle: &String) -> ProblemRecommender {
-    let file_name = "recommender";
-    if !Path::new(&file_name).exists() {
-      let res = ProblemRecommender {
-        handle: handle.clone(),
-        max_rating: UserInfoDTO::new(&handle).max_rating,
-        recommended_diff: UserInfoDTO::new(&handle).max_rating + 200, 
-        bind_problem: Problem::unit(),
-        streak: 0,
-      };
-      fs::write(&file_name, serde_json::to_string(&res).unwrap()).ok();
+  pub fn new(handle: &String, storage: &Storage, api: &Api) -> Result<ProblemRecommender, RecError> {
+    let file_name = storage.profile_file("recommender");
+    if !file_name.exists() {
+      let res = Self::fresh(handle, storage, api)?;
+      write_json(&file_name, &res)?;
     }
 
-    let res: ProblemRecommender = serde_json::from_str(&fs::read_to_string(&file_name).expect("read problem recommender"))
-             .expect("convert str to json");
-    return res;
+    let res: ProblemRecommender = read_json(&file_name)?;
+    //starting over would overwrite the practice of the other handle with the next save, codeforces handles ignore case
+    if !res.handle.eq_ignore_ascii_case(handle) {
+      return Err(RecError::Config(format!("the practice in {} belongs to {}, not {}. Give {} its own profile with \"rec profile create name {}\" and pick it with --profile name",
+                                          file_name.display(), res.handle, handle, handle, handle)));
+    }
+    Ok(res)
   }
 
-  #[allow(dead_code)]
-  pub fn to_string(&self) -> String {
-    String::from("handle: ") + &self.handle + "\n" +
-    "max_rating: " + &self.max_rating.to_string() + "\n" +
-    "recommended_diff: " + &self.recommended_diff.to_string() + "\n" +
-    "bind_problem: " + &self.bind_problem.to_string() + "\n" +
-    "streak: " + &self.streak.to_string()
+  fn fresh(handle: &str, storage: &Storage, api: &Api) -> Result<ProblemRecommender, RecError> {
+    let max_rating = UserInfoDTO::new(handle, storage, api)?.max_rating;
+    Ok(ProblemRecommender {
+      handle: handle.to_string(),
+      max_rating,
+      recommended_diff: max_rating + 200,
+      rating_deviation: rating::MAX_DEVIATION,
+      volatility: rating::INITIAL_VOLATILITY,
+      bind_problem: Problem::unit(),
+      bind_time: None,
+      paused_at: None,
+      paused_seconds: 0,
+      streak: 0,
+      tag_ratings: BTreeMap::new(),
+    })
   }
-  
-  pub fn save(&self) {
-    let file_name = "recommender";
-    fs::write(file_name, serde_json::to_string(&self).unwrap()).ok();
+
+  pub fn recommended_diff(&self) -> i64 {
+    self.recommended_diff
   }
 
-  pub fn generate_problem_pool(&self, problems: &Vec<Problem>) -> Vec<Problem> {
+  pub fn rating_deviation(&self) -> f64 {
+    self.rating_deviation
+  }
+
+  pub fn streak(&self) -> i64 {
+    self.streak
+  }
+
+  //none when no problem is bound
+  pub fn bound_problem(&self) -> Option<&Problem> {
+    (!self.bind_problem.name.is_empty()).then_some(&self.bind_problem)
+  }
+
+  pub fn bind_time(&self) -> Option<i64> {
+    self.bind_time
+  }
+
+  pub fn tag_ratings(&self) -> &BTreeMap<String, TagRating> {
+    &self.tag_ratings
+  }
+
+  //the tags rated furthest below recommended_diff, weakest first
+  pub fn weak_tags(&self) -> Vec<String> {
+    let mut res: Vec<(&String, &TagRating)> = self.tag_ratings.iter()
+      .filter(|(_, tag)| tag.solved + tag.unsolved >= MIN_TAG_RESULTS && tag.rating < self.recommended_diff)
+      .collect();
+    res.sort_by_key(|(_, tag)| tag.rating);
+    res.into_iter().take(WEAK_TAG_COUNT).map(|(name, _)| name.clone()).collect()
+  }
+
+  //the solving time of the bound problem so far, without the pauses
+  pub fn elapsed(&self) -> Option<i64> {
+    let bind_time = self.bind_time?;
+    let end = self.paused_at.unwrap_or_else(history::now);
+    Some(cmp::max(end - bind_time - self.paused_seconds, 0))
+  }
+
+  pub fn save(&self, storage: &Storage) -> Result<(), RecError> {
+    write_json(&storage.profile_file("recommender"), self)
+  }
+
+  //the problems bind picks from, and the rating they are meant to be close to
+  pub fn generate_problem_pool(&self, problems: &Vec<Problem>, storage: &Storage, api: &Api, config: &Config) -> Result<(Vec<Problem>, i64), RecError> {
     let request_diff = if self.streak <= -2 {
       self.recommended_diff - 100
     } else if self.streak >= 2 {
@@ -231,135 +443,378 @@ impl ProblemRecommender {
     } else {
       self.recommended_diff
     };
+    let (min_solved, max_solved) = match config.bind_popularity {
+      Popularity::Any => (None, None),
+      Popularity::AvoidObscure => (Some(config.obscure_solved_count), None),
+      Popularity::PreferObscure => (None, Some(config.obscure_solved_count - 1)),
+    };
+    let mut res = self.candidate_pool(problems, request_diff, min_solved, max_solved, storage, api, config)?;
+    //the popularity preference gives way rather than leaving nothing to bind
+    if res.0.is_empty() && config.bind_popularity != Popularity::Any {
+      res = self.candidate_pool(problems, request_diff, None, None, storage, api, config)?;
+    }
+
+    Ok(res)
+  }
+
+  #[allow(clippy::too_many_arguments)]
+  fn candidate_pool(&self, problems: &Vec<Problem>, request_diff: i64, min_solved: Option<i64>, max_solved: Option<i64>, storage: &Storage, api: &Api, config: &Config) -> Result<(Vec<Problem>, i64), RecError> {
+    let whitelist: Vec<String> = config.tag_whitelist.iter().map(|tag| normalize_tag(tag)).collect();
+    let blacklist: Vec<String> = config.tag_blacklist.iter().map(|tag| normalize_tag(tag)).collect();
     //consider recent div. 1 problems
     let filter_options = FilterOptions {
-      min_diff: request_diff - 50,
-      max_diff: request_diff + 50,
-      oldest_round: Some(1480),
+      min_diff: request_diff - BIND_RATING_SPREAD,
+      max_diff: request_diff + BIND_RATING_SPREAD,
+      recency: Some(Recency::Years(config.recent_years)),
       div: vec![Division::Div1, Division::Div12, Division::GlobalRound],
-      user: Some(User::new(&self.handle)),
+      user: Some(User::new(&self.handle, storage, api)?),
       pool_size: None,
+      index_range: None,
+      tags_any: whitelist.clone(),
+      tags_all: Vec::new(),
+      tags_none: blacklist.clone(),
+      min_solved,
+      max_solved,
     };
     let mut problem_pool = filter_problems(problems, &filter_options);
+    let mut target = request_diff;
     //consider all recent problems
-    if problem_pool.len() == 0 {
+    if problem_pool.is_empty() {
       let filter_options = FilterOptions {
-        min_diff: request_diff + 50,
-        max_diff: request_diff + 150,
-        oldest_round: Some(1480),
+        min_diff: request_diff + 100 - BIND_RATING_SPREAD,
+        max_diff: request_diff + 100 + BIND_RATING_SPREAD,
+        recency: Some(Recency::Years(config.recent_years)),
         div: vec![Division::Div2],
-        user: Some(User::new(&self.handle)),
+        user: Some(User::new(&self.handle, storage, api)?),
         pool_size: None,
+        index_range: None,
+        tags_any: whitelist.clone(),
+        tags_all: Vec::new(),
+        tags_none: blacklist.clone(),
+        min_solved,
+        max_solved,
       };
       problem_pool = filter_problems(problems, &filter_options);
+      target = request_diff + 100;
     }
 
-    problem_pool
+    Ok((problem_pool, target))
   }
 
-  pub fn bind_problem(&mut self, problems: &Vec<Problem>) {
-    if self.bind_problem.name != String::from("") {
-      println!("Already have a binded problem: {}", self.bind_problem.to_string());
+  pub fn bind_problem(&mut self, problems: &Vec<Problem>, options: &BindOptions, storage: &Storage, api: &Api, config: &Config) -> Result<(), RecError> {
+    if !self.bind_problem.name.is_empty() {
+      println!("Already have a binded problem: {}\n{}", self.bind_problem, self.bind_problem.problem_url(&api.site_base_url));
+      if let Some(elapsed) = self.elapsed() {
+        println!("Binded {} ago.", history::format_duration(elapsed));
+      }
     } else {
-      let problem_pool = self.generate_problem_pool(problems);
-      let mut rng = rand::thread_rng();
-      let unif = Uniform::from(0..problem_pool.len());
-      self.bind_problem = problem_pool[unif.sample(&mut rng)].clone();
-      println!("Binded problem: {}", self.bind_problem.to_string());
-      self.save();
+      let (mut problem_pool, target) = self.generate_problem_pool(problems, storage, api, config)?;
+      if problem_pool.is_empty() {
+        println!("{}", empty_pool_message(target, config));
+        return Ok(());
+      }
+      if options.weakness {
+        problem_pool = self.weakness_pool(problem_pool);
+      }
+      let candidates = selection::weigh(&problem_pool, target, config);
+      if options.debug {
+        selection::print_candidates(&candidates, target, 10);
+      }
+      self.bind_problem = selection::choose(&candidates, &mut rand::thread_rng()).clone();
+      self.bind_time = Some(history::now());
+      println!("Binded problem: {}\n{}", self.bind_problem, self.bind_problem.problem_url(&api.site_base_url));
+      self.save(storage)?;
+      self.record(Outcome::Bind, &self.bind_problem, self.recommended_diff, self.streak, None, None, storage)?;
+    }
+    Ok(())
+  }
+
+  //the candidates with one of the weak tags, or all of them when there are none
+  fn weakness_pool(&self, problem_pool: Vec<Problem>) -> Vec<Problem> {
+    let weak_tags = self.weak_tags();
+    if weak_tags.is_empty() {
+      println!("No tag is rated below recommended_diff yet, binding as usual.");
+      return problem_pool;
+    }
+    let res: Vec<Problem> = problem_pool.iter().filter(|problem| problem.tags.iter().any(|tag| weak_tags.contains(tag))).map(Problem::clone).collect();
+    if res.is_empty() {
+      println!("No problem around recommended_diff has a weak tag ({}), binding as usual.", weak_tags.join(", "));
+      return problem_pool;
     }
+    println!("Aiming at the weak tags: {}.", weak_tags.join(", "));
+    res
   }
 
-  pub fn solve_problem(&mut self) {
-    if self.bind_problem.name == String::from("") {
+  //without force the bound problem needs an accepted submission on codeforces, with force it is recorded as unverified
+  pub fn solve_problem(&mut self, force: bool, storage: &Storage, api: &Api, config: &Config) -> Result<(), RecError> {
+    if self.bind_problem.name.is_empty() {
       println!("Don't have a binded problem!");
     } else {
-      self.rating_change(true);
-      self.bind_problem = Problem::unit();
+      let verified = match self.has_accepted_submission(storage, api) {
+        Ok(verified) => verified,
+        //force is meant for problems solved off codeforces, so it doesn't need codeforces to answer either
+        Err(error) if force => {
+          println!("Can't check the submissions: {}", error);
+          false
+        },
+        Err(error) => return Err(error),
+      };
+      if !verified && !force {
+        println!("No accepted submission for {} was found, the rating is unchanged.", self.bind_problem.combined_id());
+        println!("Submit it on codeforces first, or run \"rec solved --force\" if you solved it elsewhere.");
+        return Ok(());
+      }
+      let (problem, diff_before, streak_before, duration) = (self.bind_problem.clone(), self.recommended_diff, self.streak, self.elapsed());
+      self.rating_change(true, config);
+      self.unbind();
       self.streak = cmp::max(self.streak + 1, 1);
-      self.save();
+      self.save(storage)?;
+      self.record(Outcome::Solved, &problem, diff_before, streak_before, duration, Some(verified), storage)?;
+      if !verified {
+        println!("Recorded as unverified, codeforces has no accepted submission for {}.", problem.combined_id());
+      }
       println!("Unbind the problem, rating change sucessfully!");
     }
+    Ok(())
+  }
+
+  //syncs the submissions first, the bound problem was most likely submitted a moment ago
+  fn has_accepted_submission(&self, storage: &Storage, api: &Api) -> Result<bool, RecError> {
+    update_submissionDTOs(&self.handle, false, storage, api)?;
+    Ok(get_submissionDTOs(&self.handle, storage, api)?.iter().any(|submission| {
+      submission.verdict == "OK" && submission.problem.contest_id == self.bind_problem.contest_id && submission.problem.index == self.bind_problem.index
+    }))
   }
 
-  pub fn unsolve_problem(&mut self) {
-    if self.bind_problem.name == String::from("") {
+  pub fn unsolve_problem(&mut self, storage: &Storage, api: &Api, config: &Config) -> Result<(), RecError> {
+    if self.bind_problem.name.is_empty() {
       println!("Don't have a binded problem!");
     } else {
-      self.rating_change(false);
-      User::new(&self.handle).add_unsolved_problem(&self.bind_problem);
-      self.bind_problem = Problem::unit();
+      let (problem, diff_before, streak_before, duration) = (self.bind_problem.clone(), self.recommended_diff, self.streak, self.elapsed());
+      self.rating_change(false, config);
+      User::new(&self.handle, storage, api)?.add_unsolved_problem(&self.bind_problem, storage)?;
+      self.unbind();
       self.streak = cmp::min(self.streak - 1, -1);
-      self.save();
+      self.save(storage)?;
+      self.record(Outcome::Unsolved, &problem, diff_before, streak_before, duration, None, storage)?;
       println!("Unbind the problem, rating change sucessfully!");
     }
+    Ok(())
   }
 
-  pub fn drop_problem(&mut self) {
-    if self.bind_problem.name == String::from("") {
+  pub fn drop_problem(&mut self, storage: &Storage) -> Result<(), RecError> {
+    if self.bind_problem.name.is_empty() {
       println!("Don't have a binded problem!");
     } else {
-      self.bind_problem = Problem::unit();
-      self.save();
+      let (problem, duration) = (self.bind_problem.clone(), self.elapsed());
+      self.unbind();
+      self.save(storage)?;
+      self.record(Outcome::Dropped, &problem, self.recommended_diff, self.streak, duration, None, storage)?;
       println!("Unbind the problem.");
     }
+    Ok(())
   }
 
-  fn rating_change(&mut self, solved: bool) {
-    let k_factor = 24.0;
-    let score = if solved { 1.0 } else { 0.0 };
-    let expected_score = 1.0 / (1.0 + (10.0 as f64).powf((self.bind_problem.rating - self.recommended_diff) as f64 / 400.0));
+  pub fn pause(&mut self, storage: &Storage) -> Result<(), RecError> {
+    if self.bind_problem.name.is_empty() {
+      println!("Don't have a binded problem!");
+    } else if self.paused_at.is_some() {
+      println!("The clock is already paused, resume it with \"rec resume\".");
+    } else {
+      self.paused_at = Some(history::now());
+      self.save(storage)?;
+      println!("Paused the clock of {}.", self.bind_problem);
+    }
+    Ok(())
+  }
+
+  pub fn resume(&mut self, storage: &Storage) -> Result<(), RecError> {
+    match self.paused_at {
+      Some(paused_at) => {
+        self.paused_seconds += cmp::max(history::now() - paused_at, 0);
+        self.paused_at = None;
+        self.save(storage)?;
+        println!("Resumed the clock of {}.", self.bind_problem);
+      },
+      None => println!("The clock isn't paused!"),
+    }
+    Ok(())
+  }
 
-    self.recommended_diff += (k_factor * (score - expected_score)).round() as i64;
+  fn unbind(&mut self) {
+    self.bind_problem = Problem::unit();
+    self.bind_time = None;
+    self.paused_at = None;
+    self.paused_seconds = 0;
+  }
+
+  #[allow(clippy::too_many_arguments)]
+  fn record(&self, outcome: Outcome, problem: &Problem, diff_before: i64, streak_before: i64, duration: Option<i64>, verified: Option<bool>, storage: &Storage) -> Result<(), RecError> {
+    history::append(storage, &HistoryEntry {
+      time: history::now(),
+      problem_id: problem.combined_id(),
+      rating: problem.rating,
+      outcome,
+      diff_before,
+      diff_after: self.recommended_diff,
+      streak_before,
+      streak_after: self.streak,
+      duration,
+      verified,
+    })
+  }
+
+  fn rating_change(&mut self, solved: bool, config: &Config) {
+    let score = match self.elapsed() {
+      Some(elapsed) if solved && config.time_aware_rating => time_score(elapsed, config.time_budget(self.bind_problem.rating)),
+      _ if solved => 1.0,
+      _ => 0.0,
+    };
+    let system = config.active_profile().rating_model.system();
+    //a tag seen for the first time starts from the global rating, as uncertain as a new profile
+    for name in &self.bind_problem.tags {
+      let tag = self.tag_ratings.entry(name.clone()).or_insert_with(|| TagRating {
+        rating: self.recommended_diff,
+        deviation: rating::MAX_DEVIATION,
+        volatility: rating::INITIAL_VOLATILITY,
+        solved: 0,
+        unsolved: 0,
+      });
+      let mut state = RatingState {
+        rating: tag.rating as f64,
+        deviation: tag.deviation,
+        volatility: tag.volatility,
+      };
+      system.update(&mut state, self.bind_problem.rating, score);
+      tag.rating = state.rating as i64;
+      tag.deviation = state.deviation;
+      tag.volatility = state.volatility;
+      if solved {
+        tag.solved += 1;
+      } else {
+        tag.unsolved += 1;
+      }
+    }
+
+    let mut state = RatingState {
+      rating: self.recommended_diff as f64,
+      deviation: self.rating_deviation,
+      volatility: self.volatility,
+    };
+    system.update(&mut state, self.bind_problem.rating, score);
+
+    self.recommended_diff = state.rating as i64;
+    self.rating_deviation = state.deviation;
+    self.volatility = state.volatility;
+  }
+}
+
+//a solve within the budget counts fully, a slower one counts less, down to half a solve
+fn time_score(elapsed: i64, budget: i64) -> f64 {
+  if elapsed <= budget {
+    1.0
+  } else {
+    (budget as f64 / elapsed as f64).max(0.5)
   }
 }
 
+//how recent the contest of a problem has to be
+pub enum Recency {
+  //a timestamp, contests that started before it are too old
+  Since(i64),
+  Years(i64),
+}
+
+//names the config keys that narrowed the bind pool down to nothing, bind_popularity isn't one as it gives way on its own
+fn empty_pool_message(target: i64, config: &Config) -> String {
+  let mut limits = vec![format!("recent_years ({})", config.recent_years)];
+  if !config.tag_whitelist.is_empty() {
+    limits.push(format!("tag_whitelist ({})", config.tag_whitelist.join(", ")));
+  }
+  if !config.tag_blacklist.is_empty() {
+    limits.push(format!("tag_blacklist ({})", config.tag_blacklist.join(", ")));
+  }
+  format!("Can't find an unsolved problem rated around {} to bind, try loosening {}.", target, limits.join(", "))
+}
+
+const YEAR_SECONDS: i64 = 365 * 24 * 60 * 60;
+
+impl Recency {
+  //None when that many years don't fit in a timestamp
+  pub fn years(years: i64) -> Option<Recency> {
+    years.checked_mul(YEAR_SECONDS).map(|_| Recency::Years(years))
+  }
+
+  pub fn cutoff(&self) -> i64 {
+    match self {
+      Recency::Since(time) => *time,
+      //recent_years from the config isn't checked, a huge value just means every contest counts
+      Recency::Years(years) => history::now().saturating_sub(years.saturating_mul(YEAR_SECONDS)),
+    }
+  }
+}
+
+//whether bind cares about problems that few people solved, see the obscure_solved_count config key
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Default)]
+pub enum Popularity {
+  #[default]
+  #[serde(rename = "any")]
+  Any,
+  #[serde(rename = "avoid_obscure")]
+  AvoidObscure,
+  #[serde(rename = "prefer_obscure")]
+  PreferObscure,
+}
+
+//codeforces tags are all lowercase, tags from the commandline and the config are compared the same way
+pub fn normalize_tag(tag: &str) -> String {
+  tag.trim().to_lowercase()
+}
+
 pub struct FilterOptions {
   pub min_diff: i64,
   pub max_diff: i64,
-  pub oldest_round: Option<i64>,
+  //problems of contests with an unknown start time are kept
+  pub recency: Option<Recency>,
   pub div: Vec<Division>,
   pub user: Option<User>,
   pub pool_size: Option<i64>,
+  //first and last index, compared by the letters so that D1 and D2 count as D unless the range names D1 itself
+  pub index_range: Option<(String, String)>,
+  //a problem must have at least one of tags_any, all of tags_all and none of tags_none, empty sets don't restrict
+  pub tags_any: Vec<String>,
+  pub tags_all: Vec<String>,
+  pub tags_none: Vec<String>,
+  //bounds on how many people solved the problem, problems without statistics are kept
+  pub min_solved: Option<i64>,
+  pub max_solved: Option<i64>,
 }
 
 pub fn filter_problems(problems: &Vec<Problem>, options: &FilterOptions) -> Vec<Problem> {
   let mut res: Vec<Problem> = Vec::new();
 
-  let mut div1 = false;
-  let mut div12 = false;
-  let mut div2 = false;
-  let mut edu = false;
-  let mut gl = false;
-  let mut other = false;
-
-  for element in &options.div {
-    match element {
-      Division::Div1 => div1 = true,
-      Division::Div12 => div12 = true,
-      Division::Div2 => div2 = true,
-      Division::Educational => edu = true,
-      Division::GlobalRound => gl = true,
-      Division::Other => other = true,
-    }
-  }
-
   for problem in problems {
     let mut valid = true;
 
     valid = valid && options.min_diff <= problem.rating && problem.rating <= options.max_diff;
-    valid = valid && if let Some(oldest_round) = options.oldest_round {
-      problem.contest_id >= oldest_round
+    valid = valid && match (&options.recency, problem.start_time) {
+      (Some(recency), Some(start_time)) => start_time >= recency.cutoff(),
+      _ => true,
+    };
+    valid = valid && options.div.contains(&problem.div);
+    valid = valid && if let Some((first, last)) = &options.index_range {
+      let whole = first.chars().chain(last.chars()).any(|c| c.is_ascii_digit());
+      let index: String = problem.index.to_uppercase().chars().take_while(|c| whole || c.is_ascii_alphabetic()).collect();
+      first.as_str() <= index.as_str() && index.as_str() <= last.as_str()
     } else {
       true
     };
-    valid = valid && match problem.div {
-      Division::Div1 => div1,
-      Division::Div12 => div12,
-      Division::Div2 => div2,
-      Division::Educational => edu,
-      Division::GlobalRound => gl,
-      Division::Other => other,
+    valid = valid && (options.tags_any.is_empty() || options.tags_any.iter().any(|tag| problem.tags.contains(tag)));
+    valid = valid && options.tags_all.iter().all(|tag| problem.tags.contains(tag));
+    valid = valid && !options.tags_none.iter().any(|tag| problem.tags.contains(tag));
+    valid = valid && match problem.solved_count {
+      Some(solved_count) => options.min_solved.is_none_or(|min| solved_count >= min) && options.max_solved.is_none_or(|max| solved_count <= max),
+      None => true,
     };
     valid = valid && if let Some(tmp) = &options.user {
       !tmp.accepted_problems.contains(&problem.combined_id()) && !tmp.excluded_problems.contains(&problem.combined_id()) &&
@@ -380,3 +835,275 @@ pub fn filter_problems(problems: &Vec<Problem>, options: &FilterOptions) -> Vec<
 
   res
 }
+
+#[derive(Copy, Clone, EnumString)]
+pub enum SortKey {
+  #[strum(serialize = "rating")]
+  Rating,
+  #[strum(serialize = "date")]
+  Date,
+  #[strum(serialize = "contest")]
+  Contest,
+  #[strum(serialize = "index")]
+  Index,
+  #[strum(serialize = "solvedcount")]
+  SolvedCount,
+}
+
+//ties are broken by contest and index, problems without a start time or solved count come first in ascending order
+pub fn sort_problems(problems: &mut [Problem], key: SortKey, descending: bool) {
+  problems.sort_by(|a, b| {
+    let order = match key {
+      SortKey::Rating => a.rating.cmp(&b.rating),
+      SortKey::Date => a.start_time.cmp(&b.start_time),
+      SortKey::Contest => cmp::Ordering::Equal,
+      SortKey::Index => a.index.cmp(&b.index),
+      SortKey::SolvedCount => a.solved_count.cmp(&b.solved_count),
+    };
+    let order = order.then(a.contest_id.cmp(&b.contest_id)).then(a.index.cmp(&b.index));
+    if descending { order.reverse() } else { order }
+  });
+}
+
+//a uniform sample without replacement, the same seed draws the same sample from the same problems
+pub fn sample_problems(problems: &[Problem], count: usize, seed: Option<u64>) -> Vec<Problem> {
+  let mut rng = match seed {
+    Some(seed) => StdRng::seed_from_u64(seed),
+    None => StdRng::from_rng(thread_rng()).expect("seed the random generator"),
+  };
+  problems.choose_multiple(&mut rng, count).map(|problem| problem.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::{Read, Write};
+  use std::net::TcpListener;
+  use std::thread;
+  use serde_json::json;
+  use super::*;
+
+  fn classify(contest_name: &str, contest_type: CompetitionSystem) -> String {
+    let classifier = DivisionClassifier { rules: DivisionClassifier::default_rules() };
+    classifier.classify(contest_name, contest_type, false).to_string()
+  }
+
+  #[test]
+  fn classify_plain_divisions() {
+    use CompetitionSystem::*;
+    assert_eq!(classify("Codeforces Round 900 (Div. 1)", CF), "Div. 1");
+    assert_eq!(classify("Codeforces Round 900 (Div. 2)", CF), "Div. 2");
+    assert_eq!(classify("Codeforces Round 895 (Div. 3)", ICPC), "Div. 3");
+    assert_eq!(classify("Codeforces Round 891 (div. 4)", ICPC), "Div. 4");
+    assert_eq!(classify("VK Cup 2022 Final", CF), "Other");
+  }
+
+  //the special rounds and "Div. 1 + Div. 2" come before the plain divisions they contain
+  #[test]
+  fn classify_first_matching_rule_wins() {
+    use CompetitionSystem::*;
+    assert_eq!(classify("Codeforces Round 1000 (Div. 1 + Div. 2)", CF), "Div. 1 + 2");
+    assert_eq!(classify("Codeforces Global Round 23", CF), "Global Round");
+    assert_eq!(classify("Educational Codeforces Round 150 (Rated for Div. 2)", ICPC), "Educational");
+    assert_eq!(classify("Kotlin Heroes: Episode 10", ICPC), "Kotlin Heroes");
+    assert_eq!(classify("Codeforces Round 900 (Div. 2, unrated)", CF), "Unofficial");
+  }
+
+  #[test]
+  fn classify_checks_the_contest_type() {
+    use CompetitionSystem::*;
+    assert_eq!(classify("Codeforces Round 900 (Div. 1)", ICPC), "Other");
+    assert_eq!(classify("Educational Codeforces Round 150 (Rated for Div. 2)", CF), "Div. 2");
+  }
+
+  #[test]
+  fn classify_gym() {
+    let classifier = DivisionClassifier { rules: DivisionClassifier::default_rules() };
+    assert_eq!(classifier.classify("Codeforces Round 900 (Div. 1)", CompetitionSystem::CF, true).to_string(), "Gym");
+  }
+
+  fn problems(count: i64) -> Vec<Problem> {
+    (1..=count).map(|contest_id| Problem {
+      contest_id,
+      contest_name: format!("Contest {}", contest_id),
+      contest_type: CompetitionSystem::CF,
+      div: Division::Div2,
+      index: String::from("A"),
+      name: format!("Problem {}", contest_id),
+      rating: 800,
+      tags: Vec::new(),
+      start_time: None,
+      solved_count: None,
+    }).collect()
+  }
+
+  fn ids(problems: &[Problem]) -> Vec<String> {
+    problems.iter().map(|problem| problem.combined_id()).collect()
+  }
+
+  #[test]
+  fn sample_problems_with_a_seed_is_repeatable() {
+    let pool = problems(50);
+    let first = ids(&sample_problems(&pool, 10, Some(42)));
+    assert_eq!(first, ids(&sample_problems(&pool, 10, Some(42))));
+    assert_ne!(first, ids(&sample_problems(&pool, 10, Some(43))));
+  }
+
+  #[test]
+  fn sample_problems_without_replacement() {
+    let pool = problems(20);
+    let mut sample = ids(&sample_problems(&pool, 10, Some(7)));
+    sample.sort();
+    sample.dedup();
+    assert_eq!(sample.len(), 10);
+    assert_eq!(sample_problems(&pool, 30, Some(7)).len(), 20);
+  }
+
+  fn recommender(recommended_diff: i64, tag_ratings: &[(&str, i64, i64)]) -> ProblemRecommender {
+    ProblemRecommender {
+      handle: String::from("tourist"),
+      max_rating: recommended_diff,
+      recommended_diff,
+      rating_deviation: rating::MAX_DEVIATION,
+      volatility: rating::INITIAL_VOLATILITY,
+      bind_problem: Problem::unit(),
+      bind_time: None,
+      paused_at: None,
+      paused_seconds: 0,
+      streak: 0,
+      tag_ratings: tag_ratings.iter().map(|(name, rating, results)| (name.to_string(), TagRating {
+        rating: *rating,
+        deviation: rating::MAX_DEVIATION,
+        volatility: rating::INITIAL_VOLATILITY,
+        solved: *results,
+        unsolved: 0,
+      })).collect(),
+    }
+  }
+
+  #[test]
+  fn empty_pool_message_names_the_set_limits() {
+    let config = Config { recent_years: 2, ..Config::default() };
+    assert_eq!(empty_pool_message(1900, &config), "Can't find an unsolved problem rated around 1900 to bind, try loosening recent_years (2).");
+    let config = Config { tag_blacklist: vec![String::from("dp"), String::from("graphs")], ..config };
+    assert_eq!(empty_pool_message(1900, &config), "Can't find an unsolved problem rated around 1900 to bind, try loosening recent_years (2), tag_blacklist (dp, graphs).");
+  }
+
+  #[test]
+  fn recency_years_out_of_range() {
+    assert!(Recency::years(999999999999).is_none());
+    assert!(Recency::years(3).is_some());
+    assert!(Recency::Years(i64::MAX).cutoff() < 0);
+  }
+
+  #[test]
+  fn weak_tags_weakest_first() {
+    let recommender = recommender(1600, &[("dp", 1400, 3), ("graphs", 1200, 5), ("math", 1700, 4), ("strings", 1500, 2), ("trees", 1300, 2)]);
+    assert_eq!(recommender.weak_tags(), ["graphs", "trees", "dp"]);
+  }
+
+  //a tag needs MIN_TAG_RESULTS results before it counts as weak
+  #[test]
+  fn weak_tags_need_results() {
+    let recommender = recommender(1600, &[("dp", 1000, 1), ("graphs", 1500, 2)]);
+    assert_eq!(recommender.weak_tags(), ["graphs"]);
+  }
+
+  #[test]
+  fn rating_change_rates_every_tag() {
+    let config = Config::default();
+    let mut recommender = recommender(1500, &[("dp", 1700, 3)]);
+    recommender.bind_problem = Problem {
+      rating: 1900,
+      tags: vec![String::from("dp"), String::from("math")],
+      ..Problem::unit()
+    };
+    recommender.rating_change(true, &config);
+    let (dp, math) = (&recommender.tag_ratings["dp"], &recommender.tag_ratings["math"]);
+    //a new tag starts from the global rating
+    assert_eq!((math.rating, math.solved, math.unsolved), (1522, 1, 0));
+    assert_eq!((dp.rating, dp.solved), (1718, 4));
+
+    recommender.rating_change(false, &config);
+    assert_eq!(recommender.tag_ratings["math"].unsolved, 1);
+    assert!(recommender.tag_ratings["math"].rating < 1522);
+  }
+
+  //an API that answers every call with an empty result
+  fn empty_api() -> Api {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+      for mut stream in listener.incoming().flatten() {
+        let mut request = [0; 4096];
+        let _ = stream.read(&mut request);
+        let body = r#"{"status":"OK","result":[]}"#;
+        let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+      }
+    });
+    Api::new(&Config {
+      api_base_url: format!("http://{}/api", address),
+      api_max_attempts: 1,
+      ..Config::default()
+    })
+  }
+
+  fn submission(id: i64, index: &str, verdict: &str) -> serde_json::Value {
+    json!({"id": id, "problem": {"contestId": 1900, "index": index, "name": "Problem", "rating": 1900, "tags": []}, "verdict": verdict})
+  }
+
+  #[test]
+  fn bind_pool_reaches_the_rating_spread() {
+    let dir = std::env::temp_dir().join(format!("rec-pool-test-{}", std::process::id()));
+    let storage = Storage::new(Some(dir.clone()), "default");
+    let (api, config) = (empty_api(), Config::default());
+    storage.write_cache(&storage.submissions_file("tourist"), &json!({"status": "OK", "result": []}), "test").unwrap();
+    let user = json!({"status": "OK", "result": [{"handle": "tourist", "rank": "legendary grandmaster", "rating": 3800, "maxRank": "legendary grandmaster", "maxRating": 3900}]});
+    storage.write_cache(&storage.user_info_file("tourist"), &user, "test").unwrap();
+    let problems: Vec<Problem> = [1900, 2000, 2100, 1700, 2150].iter().enumerate().map(|(i, rating)| Problem {
+      contest_id: 1900,
+      index: ((b'A' + i as u8) as char).to_string(),
+      name: String::from("Problem"),
+      rating: *rating,
+      div: Division::Div1,
+      ..Problem::unit()
+    }).collect();
+    let (pool, target) = recommender(1900, &[]).generate_problem_pool(&problems, &storage, &api, &config).unwrap();
+    assert_eq!(target, 1900);
+    assert_eq!(pool.iter().map(|problem| problem.rating).collect::<Vec<i64>>(), [1900, 2000, 2100, 1700]);
+    let candidates = selection::weigh(&pool, target, &config);
+    assert!(candidates[0].weight() > candidates[1].weight());
+    assert!(candidates[1].weight() > candidates[2].weight());
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn solve_problem_needs_an_accepted_submission() {
+    let dir = std::env::temp_dir().join(format!("rec-solve-test-{}", std::process::id()));
+    let storage = Storage::new(Some(dir.clone()), "default");
+    let (api, config) = (empty_api(), Config::default());
+    let mut recommender = recommender(1500, &[]);
+    recommender.bind_problem = Problem {
+      contest_id: 1900,
+      index: String::from("C"),
+      name: String::from("Problem"),
+      rating: 1900,
+      ..Problem::unit()
+    };
+    let submissions = json!({"status": "OK", "result": [submission(2, "C", "WRONG_ANSWER"), submission(1, "B", "OK")]});
+    storage.write_cache(&storage.submissions_file("tourist"), &submissions, "test").unwrap();
+    recommender.solve_problem(false, &storage, &api, &config).unwrap();
+    assert_eq!(recommender.bind_problem.combined_id(), "1900C");
+    assert_eq!(recommender.recommended_diff, 1500);
+    assert!(history::read(&storage).unwrap().is_empty());
+
+    let submissions = json!({"status": "OK", "result": [submission(3, "C", "OK"), submission(2, "C", "WRONG_ANSWER"), submission(1, "B", "OK")]});
+    storage.write_cache(&storage.submissions_file("tourist"), &submissions, "test").unwrap();
+    recommender.solve_problem(false, &storage, &api, &config).unwrap();
+    assert!(recommender.bind_problem.name.is_empty());
+    assert!(recommender.recommended_diff > 1500);
+    let history = history::read(&storage).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].verified, Some(true));
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
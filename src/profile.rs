@@ -0,0 +1,82 @@
+use std::fs;
+use crate::config::*;
+use crate::error::RecError;
+use crate::storage::Storage;
+
+fn valid_name(name: &str) -> bool {
+  !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn list_profiles(config: &Config, in_use: &str) {
+  let mut names: Vec<&String> = config.profiles.keys().collect();
+  let default_profile = String::from(DEFAULT_PROFILE);
+  if !config.profiles.contains_key(DEFAULT_PROFILE) {
+    names.insert(0, &default_profile);
+  }
+  for name in names {
+    let mark = if name == in_use { "*" } else { " " };
+    let handle = config.profiles.get(name).and_then(|profile| profile.handle.clone())
+                       .or_else(|| config.handle.clone())
+                       .unwrap_or_else(|| String::from("no handle"));
+    println!("{} {} ({})", mark, name, handle);
+  }
+}
+
+//in_use is the profile picked by --profile or the config, set and delete act on it rather than on the saved one
+pub fn profile_command(args: &[String], in_use: &str, config: &mut Config, storage: &Storage) -> Result<(), RecError> {
+  let name = args.get(3).map(|name| name.as_str()).unwrap_or("");
+  match args.get(2).map(|cmd| cmd.as_str()) {
+    Some("list") | None => list_profiles(config, in_use),
+    Some("create") if valid_name(name) => {
+      if config.has_profile(name) {
+        return Err(RecError::Usage(format!("Profile {} already exists!", name)));
+      } else {
+        config.profiles.insert(name.to_string(), ProfileConfig {
+          handle: args.get(4).cloned(),
+          ..ProfileConfig::default()
+        });
+        config.save()?;
+        println!("Created profile {}, switch to it with \"rec profile switch {}\".", name, name);
+      }
+    },
+    Some("create") => return Err(RecError::Usage(String::from("Profile names may only contain letters, digits, '-' and '_'!"))),
+    Some("switch") => {
+      if config.has_profile(name) {
+        config.profile = name.to_string();
+        config.save()?;
+        println!("Switched to profile {}.", name);
+      } else {
+        return Err(RecError::Usage(format!("Can't find profile {}!", name)));
+      }
+    },
+    Some("delete") => {
+      if name == config.profile || name == in_use {
+        return Err(RecError::Usage(String::from("Can't delete the profile in use, switch to another profile first!")));
+      } else if !config.has_profile(name) {
+        return Err(RecError::Usage(format!("Can't find profile {}!", name)));
+      } else {
+        config.profiles.remove(name);
+        config.save()?;
+        let dir = storage.profiles_dir().join(name);
+        if dir.exists() {
+          fs::remove_dir_all(&dir).map_err(|error| RecError::Io(dir, error))?;
+        }
+        println!("Deleted profile {}.", name);
+      }
+    },
+    //sets a key of the profile in use, e.g. "rec profile set rating_model glicko2"
+    Some("set") if args.len() == 5 => {
+      let mut profile = config.profile_config(in_use);
+      match profile.set(&args[3], &args[4]) {
+        Ok(()) => {
+          config.profiles.insert(in_use.to_string(), profile);
+          config.save()?;
+          println!("Saved {} for profile {}.", args[3], in_use);
+        },
+        Err(error) => return Err(RecError::Usage(format!("Can't set profile config: {}", error))),
+      }
+    },
+    _ => return Err(RecError::Usage(String::from("Usage: rec profile [list | create name [handle] | switch name | delete name | set key value]"))),
+  }
+  Ok(())
+}
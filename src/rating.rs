@@ -0,0 +1,171 @@
+use std::f64::consts::PI;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+//glicko-2 works on its own scale, ratings are converted with this factor around 1500
+const GLICKO_SCALE: f64 = 173.7178;
+//how far a problem's rating may be off, problem ratings are fitted on thousands of contestants
+const PROBLEM_DEVIATION: f64 = 50.0;
+pub const MIN_DEVIATION: f64 = 30.0;
+pub const MAX_DEVIATION: f64 = 350.0;
+pub const INITIAL_VOLATILITY: f64 = 0.06;
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Default, Display, EnumString)]
+pub enum RatingModel {
+  #[default]
+  #[strum(serialize = "elo")]
+  #[serde(rename = "elo")]
+  Elo,
+  #[strum(serialize = "glicko2")]
+  #[serde(rename = "glicko2")]
+  Glicko2,
+}
+
+//what a rating model knows about the user, the deviation and volatility are only used by glicko-2
+pub struct RatingState {
+  pub rating: f64,
+  pub deviation: f64,
+  pub volatility: f64,
+}
+
+pub trait RatingSystem {
+  //score is 1.0 for a solved problem and 0.0 for an unsolved one
+  fn update(&self, state: &mut RatingState, problem_rating: i64, score: f64);
+}
+
+impl RatingModel {
+  pub fn system(&self) -> Box<dyn RatingSystem> {
+    match self {
+      RatingModel::Elo => Box::new(Elo { k_factor: 24.0 }),
+      RatingModel::Glicko2 => Box::new(Glicko2 { tau: 0.5 }),
+    }
+  }
+}
+
+pub struct Elo {
+  pub k_factor: f64,
+}
+
+impl RatingSystem for Elo {
+  fn update(&self, state: &mut RatingState, problem_rating: i64, score: f64) {
+    let expected_score = 1.0 / (1.0 + 10.0_f64.powf((problem_rating as f64 - state.rating) / 400.0));
+    state.rating += (self.k_factor * (score - expected_score)).round();
+  }
+}
+
+//glicko-2 as described in http://www.glicko.net/glicko/glicko2.pdf, every solved or unsolved problem is one rating period
+pub struct Glicko2 {
+  pub tau: f64,
+}
+
+impl Glicko2 {
+  fn new_volatility(&self, phi: f64, sigma: f64, delta: f64, v: f64) -> f64 {
+    let a = (sigma * sigma).ln();
+    let f = |x: f64| {
+      let ex = x.exp();
+      ex * (delta * delta - phi * phi - v - ex) / (2.0 * (phi * phi + v + ex).powi(2)) - (x - a) / (self.tau * self.tau)
+    };
+    let mut lower = a;
+    let mut upper = if delta * delta > phi * phi + v {
+      (delta * delta - phi * phi - v).ln()
+    } else {
+      let mut k = 1.0;
+      while f(a - k * self.tau) < 0.0 {
+        k += 1.0;
+      }
+      a - k * self.tau
+    };
+    let (mut f_lower, mut f_upper) = (f(lower), f(upper));
+    while (upper - lower).abs() > 1e-6 {
+      let c = lower + (lower - upper) * f_lower / (f_upper - f_lower);
+      let f_c = f(c);
+      if f_c * f_upper <= 0.0 {
+        lower = upper;
+        f_lower = f_upper;
+      } else {
+        f_lower /= 2.0;
+      }
+      upper = c;
+      f_upper = f_c;
+    }
+
+    (lower / 2.0).exp()
+  }
+}
+
+impl RatingSystem for Glicko2 {
+  fn update(&self, state: &mut RatingState, problem_rating: i64, score: f64) {
+    let mu = (state.rating - 1500.0) / GLICKO_SCALE;
+    let phi = state.deviation / GLICKO_SCALE;
+    let mu_j = (problem_rating as f64 - 1500.0) / GLICKO_SCALE;
+    let phi_j = PROBLEM_DEVIATION / GLICKO_SCALE;
+
+    let g = 1.0 / (1.0 + 3.0 * phi_j * phi_j / (PI * PI)).sqrt();
+    let expected_score = 1.0 / (1.0 + (-g * (mu - mu_j)).exp());
+    let v = 1.0 / (g * g * expected_score * (1.0 - expected_score));
+    let delta = v * g * (score - expected_score);
+
+    let sigma = self.new_volatility(phi, state.volatility, delta, v);
+    let phi_star = (phi * phi + sigma * sigma).sqrt();
+    let new_phi = 1.0 / (1.0 / (phi_star * phi_star) + 1.0 / v).sqrt();
+    let new_mu = mu + new_phi * new_phi * g * (score - expected_score);
+
+    state.rating = (GLICKO_SCALE * new_mu + 1500.0).round();
+    state.deviation = (GLICKO_SCALE * new_phi).clamp(MIN_DEVIATION, MAX_DEVIATION);
+    state.volatility = sigma;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn state(rating: f64, deviation: f64) -> RatingState {
+    RatingState { rating, deviation, volatility: INITIAL_VOLATILITY }
+  }
+
+  //the worked example of the glicko-2 paper: a 1500 player with RD 200 beats a 1400 and loses to a 1550 and a 1700
+  #[test]
+  fn new_volatility_matches_glickman_example() {
+    let glicko = Glicko2 { tau: 0.5 };
+    let sigma = glicko.new_volatility(1.1513, 0.06, -0.4834, 1.7785);
+    assert!((sigma - 0.05999).abs() < 1e-5, "{}", sigma);
+  }
+
+  #[test]
+  fn glicko2_update_on_one_problem() {
+    let glicko = Glicko2 { tau: 0.5 };
+    let mut solved = state(1500.0, 200.0);
+    glicko.update(&mut solved, 1500, 1.0);
+    assert_eq!(solved.rating, 1586.0);
+    assert!((solved.deviation - 174.04).abs() < 0.01, "{}", solved.deviation);
+    assert!((solved.volatility - 0.06).abs() < 1e-4, "{}", solved.volatility);
+
+    let mut unsolved = state(1500.0, 200.0);
+    glicko.update(&mut unsolved, 1700, 0.0);
+    assert_eq!(unsolved.rating, 1455.0);
+    assert!((unsolved.deviation - 179.97).abs() < 0.01, "{}", unsolved.deviation);
+  }
+
+  #[test]
+  fn glicko2_deviation_stays_in_bounds() {
+    let glicko = Glicko2 { tau: 0.5 };
+    let mut settled = state(1500.0, 200.0);
+    for i in 0..500 {
+      glicko.update(&mut settled, 1500, (i % 2) as f64);
+      assert!((MIN_DEVIATION..=MAX_DEVIATION).contains(&settled.deviation), "{}", settled.deviation);
+    }
+    assert!(settled.deviation < 100.0, "{}", settled.deviation);
+  }
+
+  #[test]
+  fn elo_update() {
+    let elo = Elo { k_factor: 24.0 };
+    let mut even = state(1500.0, 0.0);
+    elo.update(&mut even, 1500, 1.0);
+    assert_eq!(even.rating, 1512.0);
+    let mut favourite = state(1900.0, 0.0);
+    elo.update(&mut favourite, 1500, 0.0);
+    assert_eq!(favourite.rating, 1878.0);
+  }
+}
@@ -0,0 +1,140 @@
+use std::cmp;
+use rand::Rng;
+use rand::distributions::{Distribution, WeightedIndex};
+use crate::config::Config;
+use crate::history;
+use crate::problem::Problem;
+
+const YEAR_SECONDS: f64 = 365.0 * 24.0 * 60.0 * 60.0;
+
+//a problem of the bind pool and the factors of its weight, each factor is raised to its bind_weight_* coefficient
+pub struct Candidate<'a> {
+  pub problem: &'a Problem,
+  pub distance: f64,
+  pub recency: f64,
+  pub popularity: f64,
+  pub division: f64,
+}
+
+impl Candidate<'_> {
+  pub fn weight(&self) -> f64 {
+    self.distance * self.recency * self.popularity * self.division
+  }
+}
+
+//distance: exp(-(d/100)^2) for d rating points off the target
+//recency: halves for every year since the contest, problems of contests with an unknown start time count as new
+//popularity: log of the solved count against the most solved problem of the pool, a negative coefficient favours obscure problems
+//division: the bind_division_preference entry, 1.0 when the division isn't listed
+pub fn weigh<'a>(pool: &'a [Problem], target: i64, config: &Config) -> Vec<Candidate<'a>> {
+  let now = history::now();
+  let most_solved = pool.iter().filter_map(|problem| problem.solved_count).max().unwrap_or(0);
+  pool.iter().map(|problem| {
+    let distance = (problem.rating - target).abs() as f64 / 100.0;
+    let age = problem.start_time.map_or(0.0, |time| (now - time).max(0) as f64 / YEAR_SECONDS);
+    let popularity = problem.solved_count.map_or(1.0, |count| ((count + 2) as f64).ln() / ((most_solved + 2) as f64).ln());
+    let division = config.bind_division_preference.get(&problem.div).copied().unwrap_or(1.0).max(0.0);
+    Candidate {
+      problem,
+      distance: (-distance * distance).exp().powf(config.bind_weight_distance),
+      recency: 0.5_f64.powf(age * config.bind_weight_recency),
+      popularity: popularity.powf(config.bind_weight_popularity),
+      division: division.powf(config.bind_weight_division),
+    }
+  }).collect()
+}
+
+pub fn choose<'a, R: Rng>(candidates: &[Candidate<'a>], rng: &mut R) -> &'a Problem {
+  match WeightedIndex::new(candidates.iter().map(|candidate| candidate.weight())) {
+    Ok(index) => candidates[index.sample(rng)].problem,
+    //every weight is zero or not finite, e.g. a division preference of 0 for the whole pool
+    Err(_) => candidates[rng.gen_range(0..candidates.len())].problem,
+  }
+}
+
+//the candidates most likely to be picked, with their share of the total weight
+pub fn print_candidates(candidates: &[Candidate], target: i64, count: usize) {
+  let total: f64 = candidates.iter().map(|candidate| candidate.weight()).sum();
+  let mut sorted: Vec<&Candidate> = candidates.iter().collect();
+  sorted.sort_by(|a, b| b.weight().total_cmp(&a.weight()));
+  println!();
+  println!("{} candidates around {}, the top {}:", candidates.len(), target, cmp::min(count, candidates.len()));
+  println!("|   id   | rating | distance | recency | popularity | division | weight | chance ");
+  for candidate in sorted.iter().take(count) {
+    let chance = if total > 0.0 { candidate.weight() / total * 100.0 } else { 0.0 };
+    println!("|{:^8}|{:^8}|{:^10.3}|{:^9.3}|{:^12.3}|{:^10.3}|{:^8.3}| {:.1}%", candidate.problem.combined_id(), candidate.problem.rating,
+             candidate.distance, candidate.recency, candidate.popularity, candidate.division, candidate.weight(), chance);
+  }
+  println!();
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::SeedableRng;
+  use rand::rngs::StdRng;
+  use crate::problem::{CompetitionSystem, Division};
+  use super::*;
+
+  fn problem(index: &str, rating: i64, div: Division, start_time: Option<i64>, solved_count: Option<i64>) -> Problem {
+    Problem {
+      contest_id: 1900,
+      contest_name: String::from("Codeforces Round 900"),
+      contest_type: CompetitionSystem::CF,
+      div,
+      index: index.to_string(),
+      name: format!("Problem {}", index),
+      rating,
+      tags: Vec::new(),
+      start_time,
+      solved_count,
+    }
+  }
+
+  #[test]
+  fn weigh_prefers_the_target_rating() {
+    let pool = [problem("A", 1900, Division::Div2, None, None), problem("B", 2000, Division::Div2, None, None)];
+    let candidates = weigh(&pool, 1900, &Config::default());
+    assert_eq!(candidates[0].distance, 1.0);
+    assert!((candidates[1].distance - (-1.0_f64).exp()).abs() < 1e-9);
+    assert!(candidates[0].weight() > candidates[1].weight());
+  }
+
+  #[test]
+  fn weigh_recency_and_popularity() {
+    let config = Config {
+      bind_weight_recency: 1.0,
+      bind_weight_popularity: 1.0,
+      ..Config::default()
+    };
+    let year_ago = history::now() - YEAR_SECONDS as i64;
+    let pool = [problem("A", 1900, Division::Div2, Some(year_ago), Some(10)), problem("B", 1900, Division::Div2, None, Some(1000))];
+    let candidates = weigh(&pool, 1900, &config);
+    assert!((candidates[0].recency - 0.5).abs() < 1e-3);
+    assert_eq!(candidates[1].recency, 1.0);
+    assert!(candidates[0].popularity < 1.0);
+    assert_eq!(candidates[1].popularity, 1.0);
+  }
+
+  #[test]
+  fn choose_skips_zero_weights() {
+    let mut config = Config::default();
+    config.bind_division_preference.insert(Division::Div3, 0.0);
+    let pool = [problem("A", 1900, Division::Div3, None, None), problem("B", 1900, Division::Div2, None, None)];
+    let candidates = weigh(&pool, 1900, &config);
+    let mut rng = StdRng::seed_from_u64(1);
+    for _ in 0..100 {
+      assert_eq!(choose(&candidates, &mut rng).index, "B");
+    }
+  }
+
+  //a pool where every weight is zero still gives a problem
+  #[test]
+  fn choose_with_only_zero_weights() {
+    let mut config = Config::default();
+    config.bind_division_preference.insert(Division::Div3, 0.0);
+    let pool = [problem("A", 1900, Division::Div3, None, None), problem("B", 1900, Division::Div3, None, None)];
+    let candidates = weigh(&pool, 1900, &config);
+    let index = &choose(&candidates, &mut StdRng::seed_from_u64(1)).index;
+    assert!(index == "A" || index == "B");
+  }
+}
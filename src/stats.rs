@@ -0,0 +1,189 @@
+use std::collections::{HashMap, HashSet};
+use strum::IntoEnumIterator;
+use crate::DTOs::DTOs::SubmissionDTO;
+use crate::history::{HistoryEntry, Outcome, format_date};
+use crate::problem::*;
+use crate::rating::RatingModel;
+
+//solved out of attempted, for one row of a table
+#[derive(Default, Clone, Copy)]
+struct Rate {
+  solved: i64,
+  total: i64,
+}
+
+impl Rate {
+  fn add(&mut self, solved: bool) {
+    self.total += 1;
+    if solved {
+      self.solved += 1;
+    }
+  }
+
+  fn describe(&self) -> String {
+    if self.total == 0 {
+      String::from("-")
+    } else {
+      format!("{}/{} ({}%)", self.solved, self.total, self.solved * 100 / self.total)
+    }
+  }
+}
+
+fn bucket(rating: i64) -> i64 {
+  rating / 100 * 100
+}
+
+//the final outcome of each practice problem, binds and drops don't count as attempts
+fn practice_results(history: &[HistoryEntry]) -> Vec<&HistoryEntry> {
+  history.iter().filter(|entry| entry.outcome == Outcome::Solved || entry.outcome == Outcome::Unsolved).collect()
+}
+
+//the problems with any submission, and whether one of them got accepted
+fn submission_results(submissions: &[SubmissionDTO]) -> HashMap<String, (i64, bool)> {
+  let mut res: HashMap<String, (i64, bool)> = HashMap::new();
+  for element in submissions {
+    let id = element.problem.contest_id.to_string() + &element.problem.index;
+    let entry = res.entry(id).or_insert((element.problem.rating, false));
+    entry.1 = entry.1 || element.verdict == "OK";
+  }
+
+  res
+}
+
+fn average_streaks(results: &[&HistoryEntry]) -> (f64, f64) {
+  let mut runs: HashMap<bool, Vec<i64>> = HashMap::new();
+  let mut last: Option<bool> = None;
+  for entry in results {
+    let solved = entry.outcome == Outcome::Solved;
+    let list = runs.entry(solved).or_default();
+    if last == Some(solved) {
+      *list.last_mut().unwrap() += 1;
+    } else {
+      list.push(1);
+    }
+    last = Some(solved);
+  }
+  let average = |list: Option<&Vec<i64>>| match list {
+    Some(list) if !list.is_empty() => list.iter().sum::<i64>() as f64 / list.len() as f64,
+    _ => 0.0,
+  };
+
+  (average(runs.get(&true)), average(runs.get(&false)))
+}
+
+fn print_trajectory(results: &[&HistoryEntry], current: i64) {
+  println!("recommended_diff over time:");
+  if results.is_empty() {
+    println!("  {:<18}{}", "now", current);
+    return;
+  }
+  //the value at the end of each day, the most recent days last
+  let mut days: Vec<(String, i64)> = vec![(String::from("start"), results[0].diff_before)];
+  for entry in results {
+    let day = format_date(entry.time);
+    match days.last_mut() {
+      Some(last) if last.0 == day => last.1 = entry.diff_after,
+      _ => days.push((day, entry.diff_after)),
+    }
+  }
+  let skip = days.len().saturating_sub(15);
+  if skip > 0 {
+    println!("  ... {} earlier days", skip);
+  }
+  for (day, diff) in &days[skip..] {
+    println!("  {:<18}{}", day, diff);
+  }
+}
+
+pub fn print_stats(recommender: &ProblemRecommender, model: RatingModel, history: &[HistoryEntry], problems: &[Problem], submissions: &[SubmissionDTO]) {
+  let problem_map: HashMap<String, &Problem> = problems.iter().map(|problem| (problem.combined_id(), problem)).collect();
+  let results = practice_results(history);
+  let count = |outcome: Outcome| history.iter().filter(|entry| entry.outcome == outcome).count();
+  let (solved_streak, unsolved_streak) = average_streaks(&results);
+
+  println!();
+  println!("recommended_diff: {}    streak: {}", recommender.recommended_diff(), recommender.streak());
+  if model == RatingModel::Glicko2 {
+    println!("rating_deviation: {:.0} (glicko2)", recommender.rating_deviation());
+  }
+  let unverified = history.iter().filter(|entry| entry.verified == Some(false)).count();
+  let unverified = if unverified > 0 { format!(" ({} unverified)", unverified) } else { String::new() };
+  println!("practice: {} solved{}, {} unsolved, {} dropped", count(Outcome::Solved), unverified, count(Outcome::Unsolved), count(Outcome::Dropped));
+  println!("average streak: {:.1} solved in a row, {:.1} unsolved in a row", solved_streak, unsolved_streak);
+  println!();
+  print_trajectory(&results, recommender.recommended_diff());
+
+  let submitted = submission_results(submissions);
+  let mut practice_buckets: HashMap<i64, Rate> = HashMap::new();
+  let mut submission_buckets: HashMap<i64, Rate> = HashMap::new();
+  let mut practice_divs: HashMap<String, Rate> = HashMap::new();
+  let mut submission_divs: HashMap<String, Rate> = HashMap::new();
+  for entry in &results {
+    let solved = entry.outcome == Outcome::Solved;
+    practice_buckets.entry(bucket(entry.rating)).or_default().add(solved);
+    if let Some(problem) = problem_map.get(&entry.problem_id) {
+      practice_divs.entry(problem.div.to_string()).or_default().add(solved);
+    }
+  }
+  for (id, (rating, solved)) in &submitted {
+    //gym problems have no rating
+    if *rating > 0 {
+      submission_buckets.entry(bucket(*rating)).or_default().add(*solved);
+    }
+    if let Some(problem) = problem_map.get(id) {
+      submission_divs.entry(problem.div.to_string()).or_default().add(*solved);
+    }
+  }
+
+  let buckets: HashSet<i64> = practice_buckets.keys().chain(submission_buckets.keys()).cloned().collect();
+  let mut buckets: Vec<i64> = buckets.into_iter().collect();
+  buckets.sort();
+  println!();
+  println!("|   rating    |  practice solved  | submissions solved ");
+  for element in buckets {
+    let rating = format!("{}-{}", element, element + 99);
+    let practice = practice_buckets.get(&element).copied().unwrap_or_default();
+    let submission = submission_buckets.get(&element).copied().unwrap_or_default();
+    println!("|{:^13}|{:^19}|{:^20}", rating, practice.describe(), submission.describe());
+  }
+
+  println!();
+  println!("|  division   |  practice solved  | submissions solved ");
+  for div in Division::iter() {
+    let practice = practice_divs.get(&div.to_string()).copied().unwrap_or_default();
+    let submission = submission_divs.get(&div.to_string()).copied().unwrap_or_default();
+    if practice.total == 0 && submission.total == 0 {
+      continue;
+    }
+    println!("|{:^13}|{:^19}|{:^20}", div.to_string(), practice.describe(), submission.describe());
+  }
+
+  print_tags(recommender, &submitted, &problem_map);
+  println!();
+}
+
+//the practiced tags, weakest first, with the submissions of problems with the tag next to them
+fn print_tags(recommender: &ProblemRecommender, submitted: &HashMap<String, (i64, bool)>, problem_map: &HashMap<String, &Problem>) {
+  let mut tags: Vec<(&String, &TagRating)> = recommender.tag_ratings().iter().collect();
+  if tags.is_empty() {
+    return;
+  }
+  tags.sort_by_key(|(_, tag)| tag.rating);
+  let mut submission_tags: HashMap<&str, Rate> = HashMap::new();
+  for (id, (_, solved)) in submitted {
+    if let Some(problem) = problem_map.get(id) {
+      for tag in &problem.tags {
+        submission_tags.entry(tag.as_str()).or_default().add(*solved);
+      }
+    }
+  }
+
+  println!();
+  println!("|           tag            | rating |  gap  |  practice solved  | submissions solved ");
+  for (name, tag) in tags {
+    let practice = Rate { solved: tag.solved, total: tag.solved + tag.unsolved };
+    let submission = submission_tags.get(name.as_str()).copied().unwrap_or_default();
+    let gap = format!("{:+}", tag.rating - recommender.recommended_diff());
+    println!("|{:^26}|{:^8}|{:^7}|{:^19}|{:^20}", name, tag.rating, gap, practice.describe(), submission.describe());
+  }
+}
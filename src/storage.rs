@@ -0,0 +1,170 @@
+use std::fs;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use crate::config::DEFAULT_PROFILE;
+use crate::error::RecError;
+use crate::history;
+
+//when a cache file was fetched and the API method that answered
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CacheMeta {
+  pub fetched_at: i64,
+  pub source: String,
+}
+
+//caches can be thrown away and refetched and are shared by every profile, state is the profile's practice progress
+pub struct Storage {
+  cache_dir: PathBuf,
+  data_dir: PathBuf,
+  profile_dir: PathBuf,
+}
+
+impl Storage {
+  pub fn new(data_dir: Option<PathBuf>, profile: &str) -> Storage {
+    let (cache_dir, data_dir) = match data_dir {
+      Some(dir) => (dir.join("cache"), dir),
+      None => (
+        dirs::cache_dir().unwrap_or_else(|| PathBuf::from(".")).join("rec"),
+        dirs::data_dir().unwrap_or_else(|| PathBuf::from(".")).join("rec"),
+      ),
+    };
+    Storage {
+      profile_dir: data_dir.join("profiles").join(profile),
+      cache_dir,
+      data_dir,
+    }
+  }
+
+  pub fn cache_file(&self, name: &str) -> PathBuf {
+    Self::prepare(self.cache_dir.join(name))
+  }
+
+  pub fn profile_file(&self, name: &str) -> PathBuf {
+    Self::prepare(self.profile_dir.join(name))
+  }
+
+  pub fn cache_dir(&self) -> &Path {
+    &self.cache_dir
+  }
+
+  pub fn profiles_dir(&self) -> PathBuf {
+    self.data_dir.join("profiles")
+  }
+
+  pub fn submissions_file(&self, handle: &str) -> PathBuf {
+    self.cache_file(&("submissions/".to_owned() + handle))
+  }
+
+  pub fn gym_file(&self, contest_id: i64) -> PathBuf {
+    self.cache_file(&format!("gym/{}", contest_id))
+  }
+
+  pub fn user_info_file(&self, handle: &str) -> PathBuf {
+    self.cache_file(&("user_info/".to_owned() + handle))
+  }
+
+  //write a fetched cache file together with its metadata
+  pub fn write_cache<T: Serialize>(&self, path: &Path, value: &T, source: &str) -> Result<(), RecError> {
+    write_json(path, value)?;
+    self.record_fetch(path, source)
+  }
+
+  //mark a cache file as fetched now, for syncs that found nothing new to write
+  pub fn record_fetch(&self, path: &Path, source: &str) -> Result<(), RecError> {
+    let mut metadata = self.read_metadata();
+    metadata.insert(self.cache_key(path), CacheMeta {
+      fetched_at: history::now(),
+      source: source.to_string(),
+    });
+    write_json(&self.cache_file("metadata"), &metadata)
+  }
+
+  //caches written before the metadata existed fall back to the modification time of the file
+  pub fn cache_meta(&self, path: &Path) -> Option<CacheMeta> {
+    if let Some(meta) = self.read_metadata().remove(&self.cache_key(path)) {
+      return Some(meta);
+    }
+    let modified = fs::metadata(path).and_then(|metadata| metadata.modified()).ok()?;
+    Some(CacheMeta {
+      fetched_at: modified.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64,
+      source: String::from("unknown"),
+    })
+  }
+
+  //seconds since the cache file was fetched, none if it doesn't exist
+  pub fn cache_age(&self, path: &Path) -> Option<i64> {
+    if !path.exists() {
+      return None;
+    }
+    self.cache_meta(path).map(|meta| history::now() - meta.fetched_at)
+  }
+
+  fn read_metadata(&self) -> BTreeMap<String, CacheMeta> {
+    read_json(&self.cache_dir.join("metadata")).unwrap_or_default()
+  }
+
+  //"submissions/handle" for a path inside the cache directory
+  fn cache_key(&self, path: &Path) -> String {
+    let relative = path.strip_prefix(&self.cache_dir).unwrap_or(path);
+    relative.components().map(|component| component.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+  }
+
+  fn prepare(path: PathBuf) -> PathBuf {
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent).ok();
+    }
+    path
+  }
+
+  fn move_file(old: &Path, new: PathBuf) {
+    if !old.is_file() || new.exists() {
+      return;
+    }
+    let new = Self::prepare(new);
+    if fs::rename(old, &new).is_err() && fs::copy(old, &new).is_ok() {
+      fs::remove_file(old).ok();
+    }
+    if new.exists() {
+      println!("Moved {} to {}.", old.display(), new.display());
+    }
+  }
+
+  //older versions kept every file in the working directory, they are moved over until a run that knows the handle
+  //leaves the migrated marker behind, the submissions and user_info files can't be placed without the handle
+  pub fn migrate(&self, handle: Option<&str>) {
+    let marker = self.data_dir.join("migrated");
+    if !marker.exists() {
+      fs::create_dir_all(&self.data_dir).ok();
+      Self::move_file(Path::new("problems"), self.cache_dir.join("problems"));
+      Self::move_file(Path::new("contests"), self.cache_dir.join("contests"));
+      for name in ["recommender", "upsolve", "excluded"] {
+        Self::move_file(Path::new(name), self.profile_dir.join(name));
+      }
+      if let Some(handle) = handle {
+        Self::move_file(Path::new(handle), self.cache_dir.join("submissions").join(handle));
+        Self::move_file(Path::new("user_info"), self.cache_dir.join("user_info").join(handle));
+        fs::write(&marker, "").ok();
+      }
+    }
+    //before profiles existed the state sat directly in the data directory, it becomes the default profile
+    for name in ["recommender", "upsolve", "excluded"] {
+      Self::move_file(&self.data_dir.join(name), self.profiles_dir().join(DEFAULT_PROFILE).join(name));
+    }
+  }
+}
+
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, RecError> {
+  let content = fs::read_to_string(path).map_err(|error| RecError::Io(path.to_path_buf(), error))?;
+  serde_json::from_str(&content).map_err(|error| RecError::json(&path.display().to_string(), error))
+}
+
+//write to a temporary file first, so an interrupted write never leaves a truncated file behind
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), RecError> {
+  let content = serde_json::to_string(value).map_err(|error| RecError::json(&path.display().to_string(), error))?;
+  let tmp = path.with_extension("tmp");
+  fs::write(&tmp, content).map_err(|error| RecError::Io(tmp.clone(), error))?;
+  fs::rename(&tmp, path).map_err(|error| RecError::Io(path.to_path_buf(), error))
+}
@@ -0,0 +1,114 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::thread::sleep;
+use std::time::Duration;
+use crate::DTOs::DTOs::*;
+use crate::api::Api;
+use crate::config::Config;
+use crate::error::RecError;
+use crate::history::{self, format_duration, format_time, parse_duration};
+use crate::problem::ProblemRecommender;
+use crate::storage::Storage;
+
+//seconds between two polls, codeforces allows one call every 2 seconds but watch may run for hours
+const DEFAULT_INTERVAL: i64 = 30;
+const MIN_INTERVAL: i64 = 10;
+//submissions fetched per poll, a new verdict on the bound problem is among the newest ones
+const POLL_COUNT: usize = 10;
+
+//poll the submissions of the handle until the bound problem gets an accepted one, or the deadline passes
+pub fn watch_command(args: &[String], handle: &str, storage: &Storage, api: &Api, config: &Config) -> Result<(), RecError> {
+  let mut interval = DEFAULT_INTERVAL;
+  let mut deadline: Option<i64> = None;
+  let mut i = 2;
+  while i < args.len() {
+    let value = args.get(i + 1).map(|value| value.as_str()).ok_or_else(|| RecError::Usage(format!("{} needs a value.", args[i])))?;
+    match args[i].as_str() {
+      "--interval" => {
+        interval = value.parse::<i64>().ok().filter(|interval| *interval >= MIN_INTERVAL)
+                        .ok_or_else(|| RecError::Usage(format!("Invalid interval \"{}\", it has to be at least {} seconds.", value, MIN_INTERVAL)))?;
+      },
+      "--deadline" => {
+        deadline = Some(parse_duration(value).ok_or_else(|| RecError::Usage(format!("Invalid deadline \"{}\", use 90, 45m, 2h or 1h30m.", value)))?);
+      },
+      _ => return Err(RecError::Usage(format!("Unknown watch flag \"{}\", see \"rec help\".", args[i]))),
+    }
+    i += 2;
+  }
+
+  let recommender = ProblemRecommender::new(&handle.to_string(), storage, api)?;
+  let Some(problem) = recommender.bound_problem() else {
+    println!("Don't have a binded problem!");
+    return Ok(());
+  };
+  let problem_id = problem.combined_id();
+  let started = history::now();
+  match deadline {
+    Some(deadline) => println!("Watching {} every {}s, unsolved after {} of solving time. Press Ctrl-C to stop.", problem_id, interval, format_duration(deadline)),
+    None => println!("Watching {} every {}s. Press Ctrl-C to stop.", problem_id, interval),
+  }
+
+  //the last verdict reported for each submission
+  let mut reported: HashMap<i64, Option<String>> = HashMap::new();
+  loop {
+    //solved, dropped or bound again from another terminal in the meantime
+    let mut recommender = ProblemRecommender::new(&handle.to_string(), storage, api)?;
+    let Some(problem) = recommender.bound_problem() else {
+      println!("{} isn't binded anymore, stop watching.", problem_id);
+      return Ok(());
+    };
+    if problem.combined_id() != problem_id {
+      println!("{} isn't binded anymore, stop watching.", problem_id);
+      return Ok(());
+    }
+
+    let submissions = match get_verdictDTOs(handle, POLL_COUNT, api) {
+      Ok(submissions) => submissions,
+      //a long watch outlives a short outage, the next poll tries again
+      Err(error) if error.is_transient() || matches!(error, RecError::Network(..)) => {
+        println!("Can't check the submissions: {}", error);
+        Vec::new()
+      },
+      Err(error) => return Err(error),
+    };
+    let bind_time = recommender.bind_time().unwrap_or(0);
+    let mut accepted = false;
+    for submission in submissions.iter().rev() {
+      if submission.contest_id != problem.contest_id || submission.index != problem.index || submission.time < bind_time {
+        continue;
+      }
+      if reported.get(&submission.id) != Some(&submission.verdict) {
+        println!("[{}] {}: {}", format_time(submission.time), problem_id, describe(submission));
+        reported.insert(submission.id, submission.verdict.clone());
+      }
+      accepted = accepted || submission.verdict.as_deref() == Some("OK");
+    }
+    if accepted {
+      return recommender.solve_problem(false, storage, api, config);
+    }
+
+    //the solving time leaves out pauses, a problem bound before the clock existed counts from the start of the watch
+    let elapsed = recommender.elapsed().unwrap_or_else(|| history::now() - started);
+    let mut wait = interval;
+    if let Some(deadline) = deadline {
+      if elapsed >= deadline {
+        println!("The deadline of {} for {} has passed.", format_duration(deadline), problem_id);
+        return recommender.unsolve_problem(storage, api, config);
+      }
+      wait = cmp::min(wait, deadline - elapsed);
+    }
+    sleep(Duration::from_secs(wait as u64));
+  }
+}
+
+//"WRONG_ANSWER on test 4", "OK (52 tests)", "in queue"
+fn describe(submission: &VerdictDTO) -> String {
+  match submission.verdict.as_deref() {
+    None => String::from("in queue"),
+    Some("OK") => format!("OK ({} tests)", submission.passed_test_count),
+    Some(verdict @ ("WRONG_ANSWER" | "TIME_LIMIT_EXCEEDED" | "MEMORY_LIMIT_EXCEEDED" | "RUNTIME_ERROR" | "IDLENESS_LIMIT_EXCEEDED" | "PRESENTATION_ERROR")) => {
+      format!("{} on test {}", verdict, submission.passed_test_count + 1)
+    },
+    Some(verdict) => verdict.to_string(),
+  }
+}